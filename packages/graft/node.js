@@ -4,6 +4,7 @@ const glob = require("glob");
 module.exports.fetchJson = async function (url, noSsl, options) {
   const { default: fetch } = await import("node-fetch");
   let optionsObj = Object.fromEntries(options);
+  optionsObj.headers = Object.fromEntries(optionsObj.headers);
   if (noSsl) {
     const { Agent } = await import("https");
     const agent = new Agent({ rejectUnauthorized: false });
@@ -19,3 +20,22 @@ module.exports.fetchJson = async function (url, noSsl, options) {
 module.exports.readFileToString = function (path) {
   return fs.readFileSync(path, { encoding: "utf8" });
 };
+
+module.exports.modifiedMs = function (path) {
+  try {
+    return fs.statSync(path).mtimeMs;
+  } catch (err) {
+    if (err.code === "ENOENT") {
+      return undefined;
+    }
+    throw err;
+  }
+};
+
+module.exports.sleep = function (ms) {
+  return new Promise((resolve) => setTimeout(resolve, ms));
+};
+
+module.exports.glob = function (pattern) {
+  return glob.sync(pattern, { nodir: true });
+};
@@ -1,11 +1,20 @@
 use std::path::PathBuf;
 
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 
 #[derive(Subcommand)]
 pub enum Mode {
     Typescript,
     All,
+    Watch,
+    Check,
+    List,
+    Diff {
+        old: PathBuf,
+        new: PathBuf,
+        #[arg(long)]
+        json: bool,
+    },
 }
 
 #[derive(Parser)]
@@ -18,4 +27,22 @@ pub struct Base {
     pub config_location: Option<PathBuf>,
     #[arg(short, long, action = clap::ArgAction::Count)]
     pub verbose: u8,
+    #[command(flatten)]
+    pub selection: Selection,
+}
+
+/// Which `generates` entries to run
+#[derive(Args, Default)]
+pub struct Selection {
+    #[arg(long = "only", global = true)]
+    pub only: Vec<String>,
+    #[arg(long = "skip", global = true)]
+    pub skip: Vec<String>,
+}
+
+impl Selection {
+    pub fn includes(&self, name: &str) -> bool {
+        (self.only.is_empty() || self.only.iter().any(|only| only == name))
+            && !self.skip.iter().any(|skip| skip == name)
+    }
 }
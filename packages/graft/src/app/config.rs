@@ -1,14 +1,41 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::path::{Path, PathBuf};
 
-use eyre::Result;
+use convert_case::{Case, Casing};
+use eyre::{eyre, Result};
 use regex::Captures;
 use regex_macro::regex;
 use serde::{de::Visitor, Deserialize};
 use url::Url;
 
+use crate::app::cli::Selection;
 use crate::{cross, util};
 
+fn interpolate_envvars<E>(st: &str) -> std::result::Result<String, E>
+where
+    E: serde::de::Error,
+{
+    // FIXME: This' pretty hacky, but I can't think of a better way to deal with `std::env::var`s `Result` inside `replace_all` right now
+    const ENVVAR_NOT_FOUND: &str = "{{ENVVAR NOT FOUND}}";
+
+    let envvar_interpolator = regex!(r#"\{\{(\w+)\}\}"#);
+
+    let interpolated = envvar_interpolator.replace_all(st, |captures: &Captures<'_>| {
+        let envvar_key = captures.get(1).expect("first capture defined in envvar_interpolator");
+        let envvar_key = envvar_key.as_str();
+        cross::env::var(envvar_key).unwrap_or_else(|_| {
+            eprintln!("Couldn't find environment variable with name \"{envvar_key}\" while interpolating schema");
+            ENVVAR_NOT_FOUND.to_owned()
+        })
+    });
+
+    if interpolated.contains(ENVVAR_NOT_FOUND) {
+        return Err(E::custom("Missing environment variable"));
+    }
+
+    Ok(interpolated.into_owned())
+}
+
 #[derive(Debug)]
 pub struct EnvvarUrl(pub Url);
 
@@ -27,23 +54,7 @@ impl<'de> Visitor<'de> for EnvvarUrlVisitor {
     where
         E: serde::de::Error,
     {
-        // FIXME: This' pretty hacky, but I can't think of a better way to deal with `std::env::var`s `Result` inside `replace_all` right now
-        const ENVVAR_NOT_FOUND: &str = "{{ENVVAR NOT FOUND}}";
-
-        let envvar_interpolator = regex!(r#"\{\{(\w+)\}\}"#);
-
-        let url = envvar_interpolator.replace_all(st, |captures: &Captures<'_>| {
-            let envvar_key = captures.get(1).expect("first capture defined in envvar_interpolator");
-            let envvar_key = envvar_key.as_str();
-            cross::env::var(envvar_key).unwrap_or_else(|_| {
-                eprintln!("Couldn't find environment variable with name \"{envvar_key}\" while interpolating schema");
-                ENVVAR_NOT_FOUND.to_owned()
-            })
-        });
-
-        if url.contains(ENVVAR_NOT_FOUND) {
-            return Err(E::custom("Missing environment variable"));
-        }
+        let url = interpolate_envvars::<E>(st)?;
 
         let url = Url::try_from(url.as_ref()).map_err(|err| E::custom(err))?;
 
@@ -60,6 +71,42 @@ impl<'de> Deserialize<'de> for EnvvarUrl {
     }
 }
 
+/// A string possibly containing secrets interpolated from envvars, so it is redacted from `Debug` output.
+pub struct EnvvarString(pub String);
+
+impl std::fmt::Debug for EnvvarString {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("EnvvarString(..)")
+    }
+}
+
+struct EnvvarStringVisitor;
+
+impl<'de> Visitor<'de> for EnvvarStringVisitor {
+    type Value = EnvvarString;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter
+            .write_str("a string possibly containing envvars for interpolation, e.g.: {{TOKEN}}")
+    }
+
+    fn visit_str<E>(self, st: &str) -> std::result::Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(EnvvarString(interpolate_envvars(st)?))
+    }
+}
+
+impl<'de> Deserialize<'de> for EnvvarString {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_string(EnvvarStringVisitor)
+    }
+}
+
 #[derive(Deserialize, Debug)]
 #[serde(deny_unknown_fields, rename_all = "camelCase")]
 pub struct SchemaGenOut {
@@ -72,12 +119,45 @@ pub struct SchemaGenOut {
 #[derive(Deserialize, Debug)]
 #[serde(deny_unknown_fields, rename_all = "camelCase")]
 pub struct SchemaGenPlan {
-    pub url: EnvvarUrl,
+    pub url: Option<EnvvarUrl>,
+    pub path: Option<PathBuf>,
     #[serde(default)]
     pub no_ssl: bool,
+    /// Extra headers sent with the introspection request
+    #[serde(default)]
+    pub headers: HashMap<String, EnvvarString>,
+    /// Report how the schema changed since the previous `out.ast` before overwriting it
+    #[serde(default)]
+    pub diff: bool,
     pub out: SchemaGenOut,
 }
 
+pub enum SchemaSource<'a> {
+    Url(&'a Url),
+    /// Either an SDL file or an introspection response JSON file
+    Path(&'a Path),
+}
+
+impl SchemaGenPlan {
+    pub fn headers(&self) -> HashMap<&str, &str> {
+        self.headers
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.0.as_str()))
+            .collect()
+    }
+
+    pub fn source(&self) -> Result<SchemaSource<'_>> {
+        match (&self.url, &self.path) {
+            (Some(url), None) => Ok(SchemaSource::Url(&url.0)),
+            (None, Some(path)) => Ok(SchemaSource::Path(path)),
+            (Some(_), Some(_)) => Err(eyre!(
+                "Schema plan must have either a `url` or a `path`, not both"
+            )),
+            (None, None) => Err(eyre!("Schema plan must have either a `url` or a `path`")),
+        }
+    }
+}
+
 #[derive(Deserialize, Debug)]
 pub struct DocumentImport(String, String);
 
@@ -97,6 +177,140 @@ impl Default for DocumentImport {
     }
 }
 
+/// How GraphQL enums are written out
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum EnumStyle {
+    /// `export enum Episode { NewHope = "NEW_HOPE" }`
+    #[default]
+    Enum,
+    /// `export type Episode = "NEW_HOPE"`
+    Union,
+    /// `export const Episode = { NewHope: "NEW_HOPE" } as const`, plus a type of its values
+    ConstObject,
+    /// `export const enum Episode { NewHope = "NEW_HOPE" }`
+    ConstEnum,
+}
+
+/// How GraphQL enum value names are turned into Typescript member names
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum MemberCase {
+    Preserve,
+    #[default]
+    Pascal,
+    Camel,
+}
+
+impl MemberCase {
+    pub fn apply(self, name: &str) -> String {
+        match self {
+            MemberCase::Preserve => name.to_owned(),
+            MemberCase::Pascal => name.to_case(Case::Pascal),
+            MemberCase::Camel => name.to_case(Case::Camel),
+        }
+    }
+}
+
+/// A type to import into the generated module for a scalar mapping to use
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct TypeImport {
+    pub from: String,
+    pub name: String,
+}
+
+/// The Typescript a scalar maps to in the config, where `type` is used for both inputs and outputs
+/// unless `input` or `output` overrides it. `zod` is the schema that validates the input type.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ScalarMapping {
+    #[serde(rename = "type")]
+    type_expression: Option<String>,
+    input: Option<String>,
+    output: Option<String>,
+    import: Option<TypeImport>,
+    zod: Option<String>,
+}
+
+/// Either a bare Typescript type expression, or a mapping with separate input and output types and an import
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum ScalarType {
+    Expression(String),
+    Mapping(ScalarMapping),
+}
+
+impl ScalarType {
+    pub fn output(&self) -> Option<&str> {
+        match self {
+            ScalarType::Expression(expression) => Some(expression),
+            ScalarType::Mapping(mapping) => mapping
+                .output
+                .as_deref()
+                .or(mapping.type_expression.as_deref()),
+        }
+    }
+
+    /// The type accepted in arguments and input objects, if it differs from the output type
+    pub fn distinct_input(&self) -> Option<&str> {
+        match self {
+            ScalarType::Expression(_) => None,
+            ScalarType::Mapping(mapping) => mapping
+                .input
+                .as_deref()
+                .or(mapping.type_expression.as_deref())
+                .filter(|input| Some(*input) != self.output()),
+        }
+    }
+
+    pub fn import(&self) -> Option<&TypeImport> {
+        match self {
+            ScalarType::Expression(_) => None,
+            ScalarType::Mapping(mapping) => mapping.import.as_ref(),
+        }
+    }
+
+    /// A Zod expression, e.g. `z.string().datetime()`
+    pub fn zod(&self) -> Option<&str> {
+        match self {
+            ScalarType::Expression(_) => None,
+            ScalarType::Mapping(mapping) => mapping.zod.as_deref(),
+        }
+    }
+}
+
+/// Rejects mappings that leave a scalar without an output type, naming the scalar
+fn deserialize_scalar_newtypes<'de, D>(
+    deserializer: D,
+) -> std::result::Result<Option<HashMap<String, ScalarType>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let scalar_newtypes = Option::<HashMap<String, ScalarType>>::deserialize(deserializer)?;
+
+    if let Some((name, _)) = scalar_newtypes
+        .iter()
+        .flatten()
+        .find(|(_, scalar_type)| scalar_type.output().is_none())
+    {
+        return Err(serde::de::Error::custom(format!(
+            "scalar \"{name}\" needs a `type` or an `output`"
+        )));
+    }
+
+    Ok(scalar_newtypes)
+}
+
+/// The library React hooks are generated for
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReactHooksLibrary {
+    #[serde(rename = "@apollo/client")]
+    Apollo,
+    #[serde(rename = "urql")]
+    Urql,
+}
+
 mod default_options {
     pub fn selection_set_suffix() -> String {
         "SelectionSet".to_owned()
@@ -112,13 +326,32 @@ mod default_options {
 pub struct TypescriptOptions {
     #[serde(default)]
     pub document_import: DocumentImport,
-    pub scalar_newtypes: Option<HashMap<String, String>>,
+    #[serde(default, deserialize_with = "deserialize_scalar_newtypes")]
+    pub scalar_newtypes: Option<HashMap<String, ScalarType>>,
     #[serde(default)]
     pub documents_hide_operation_name: bool,
     #[serde(default = "default_options::selection_set_suffix")]
     pub selection_set_suffix: String,
     #[serde(default = "default_options::arguments_suffix")]
     pub arguments_suffix: String,
+    #[serde(default)]
+    pub enum_style: EnumStyle,
+    #[serde(default)]
+    pub enum_member_case: MemberCase,
+    /// Also generate Zod schemas for scalars, enums, input objects and operation arguments
+    #[serde(default)]
+    pub zod: bool,
+    /// Also generate a `getSdk(requester)` function with a method per operation
+    #[serde(default)]
+    pub sdk: bool,
+    /// Also generate a React hook per operation, e.g. `useHeroQuery`, for this library
+    pub react_hooks: Option<ReactHooksLibrary>,
+}
+
+impl TypescriptOptions {
+    pub fn scalar_type(&self, name: &str) -> Option<&ScalarType> {
+        self.scalar_newtypes.as_ref()?.get(name)
+    }
 }
 
 impl Default for TypescriptOptions {
@@ -129,49 +362,89 @@ impl Default for TypescriptOptions {
             documents_hide_operation_name: Default::default(),
             selection_set_suffix: default_options::selection_set_suffix(),
             arguments_suffix: default_options::arguments_suffix(),
+            enum_style: EnumStyle::default(),
+            enum_member_case: MemberCase::default(),
+            zod: false,
+            sdk: false,
+            react_hooks: None,
         }
     }
 }
 
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrManyPatterns {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl From<OneOrManyPatterns> for DocumentPaths {
+    fn from(patterns: OneOrManyPatterns) -> Self {
+        match patterns {
+            OneOrManyPatterns::One(pattern) => Self(vec![pattern]),
+            OneOrManyPatterns::Many(patterns) => Self(patterns),
+        }
+    }
+}
+
+/// A list of document paths and glob patterns. Patterns beginning with `!` exclude matching files.
 #[derive(Deserialize, Debug)]
-pub struct DocumentPaths(Vec<PathBuf>);
+#[serde(from = "OneOrManyPatterns")]
+pub struct DocumentPaths(Vec<String>);
 
 #[cfg(test)]
 impl<const N: usize> From<[&str; N]> for DocumentPaths {
     fn from(paths: [&str; N]) -> Self {
-        Self(paths.into_iter().map(PathBuf::from).collect())
+        Self(paths.into_iter().map(ToOwned::to_owned).collect())
     }
 }
 
 impl DocumentPaths {
-    pub fn resolve_to_full_document_string(
-        mut self,
+    fn pattern_with_possible_prefix(
         config_location: Option<&Path>,
-    ) -> Result<Option<String>> {
-        if self.0.is_empty() {
-            return Ok(None);
-        }
+        pattern: &str,
+    ) -> Result<String> {
+        let pattern = util::path_with_possible_prefix(config_location, Path::new(pattern));
+
+        pattern
+            .to_str()
+            .map(ToOwned::to_owned)
+            .ok_or_else(|| eyre!("Couldn't convert document pattern to UTF-8: {pattern:?}"))
+    }
 
-        self.0.sort();
-        self.0.reverse();
+    pub fn resolve(&self, config_location: Option<&Path>) -> Result<Vec<PathBuf>> {
+        let mut included = BTreeSet::new();
+        let mut excluded = BTreeSet::new();
+
+        for pattern in &self.0 {
+            if let Some(pattern) = pattern.strip_prefix('!') {
+                let pattern = Self::pattern_with_possible_prefix(config_location, pattern)?;
+                excluded.extend(cross::fs::glob(&pattern)?);
+            } else {
+                let pattern = Self::pattern_with_possible_prefix(config_location, pattern)?;
+                let matches = cross::fs::glob(&pattern)?;
+                if matches.is_empty() {
+                    return Err(eyre!("Document pattern '{pattern}' didn't match any files"));
+                }
+                included.extend(matches);
+            }
+        }
 
-        let full_document_string = self
-            .0
-            .into_iter()
-            .map(|document_path| -> Result<String> {
-                let document_path = crate::util::path_with_possible_prefix(
-                    config_location,
-                    document_path.as_path(),
-                );
+        let mut document_paths = included.difference(&excluded).cloned().collect::<Vec<_>>();
+        document_paths.reverse();
 
-                let document_string = cross::fs::read_to_string(document_path)?;
+        Ok(document_paths)
+    }
 
-                Ok(document_string)
+    /// Reads every document file, with its path
+    pub fn read(&self, config_location: Option<&Path>) -> Result<Vec<(PathBuf, String)>> {
+        self.resolve(config_location)?
+            .into_iter()
+            .map(|path| {
+                let text = cross::fs::read_to_string(&path)?;
+                Ok((path, text))
             })
-            .collect::<Result<Vec<_>>>()?
-            .join("\n");
-
-        Ok(Some(full_document_string))
+            .collect()
     }
 }
 
@@ -182,6 +455,9 @@ pub struct TypescriptGenPlan {
     #[serde(rename = "documents")]
     pub document_paths: Option<DocumentPaths>,
     pub out: PathBuf,
+    /// Write a module next to each document file, leaving only schema types in `out`
+    #[serde(rename = "nearOperationFile", default)]
+    pub near_operation_file: bool,
     #[serde(default)]
     pub options: TypescriptOptions,
 }
@@ -195,20 +471,458 @@ pub struct GenPlans {
     pub typescript_gen_plan: Option<TypescriptGenPlan>,
 }
 
+impl GenPlans {
+    /// Everything this entry reads, with document patterns resolved to files
+    pub fn planned_inputs(&self, config_location: Option<&Path>) -> Result<Vec<String>> {
+        let mut inputs = vec![];
+
+        if let Some(schema_gen_plan) = &self.schema_gen_plan {
+            match schema_gen_plan.source()? {
+                SchemaSource::Url(url) => inputs.push(url.to_string()),
+                SchemaSource::Path(path) => inputs.push(path.display().to_string()),
+            }
+        }
+
+        if let Some(typescript_gen_plan) = &self.typescript_gen_plan {
+            inputs.push(typescript_gen_plan.ast.display().to_string());
+            if let Some(document_paths) = &typescript_gen_plan.document_paths {
+                inputs.extend(
+                    document_paths
+                        .resolve(config_location)?
+                        .into_iter()
+                        .map(|path| path.display().to_string()),
+                );
+            }
+        }
+
+        Ok(inputs)
+    }
+
+    /// Everything this entry writes, including the modules written next to document files
+    pub fn planned_outputs(&self, config_location: Option<&Path>) -> Result<Vec<PathBuf>> {
+        let mut outputs = vec![];
+
+        if let Some(schema_gen_plan) = &self.schema_gen_plan {
+            outputs.extend(schema_gen_plan.out.ast_path.clone());
+            outputs.extend(schema_gen_plan.out.json_path.clone());
+        }
+
+        if let Some(typescript_gen_plan) = &self.typescript_gen_plan {
+            outputs.push(typescript_gen_plan.out.clone());
+            if typescript_gen_plan.near_operation_file {
+                if let Some(document_paths) = &typescript_gen_plan.document_paths {
+                    let mut document_paths = document_paths.resolve(config_location)?;
+                    document_paths.sort();
+                    outputs.extend(
+                        document_paths
+                            .iter()
+                            .map(|path| util::near_operation_file_path(path)),
+                    );
+                }
+            }
+        }
+
+        Ok(outputs)
+    }
+}
+
 #[derive(Deserialize, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct Config {
-    pub generates: HashMap<String, GenPlans>,
+    pub generates: BTreeMap<String, GenPlans>,
+}
+
+/// Config file names, in the order they're looked for within a directory
+const CONFIG_FILE_NAMES: [&str; 5] = [
+    ".graft.yml",
+    ".graft.yaml",
+    ".graft.json",
+    "graft.toml",
+    "package.json",
+];
+
+#[derive(Deserialize)]
+struct PackageJson {
+    graft: Option<Config>,
 }
 
 impl Config {
-    pub fn load(dir: Option<&Path>) -> Result<Self> {
-        let config_name = ".graft.yml";
+    /// Finds the config file in `dir`, or else in the current directory or the nearest of its parents that has one
+    pub fn find(dir: Option<&Path>) -> Result<PathBuf> {
+        match dir {
+            Some(dir) => Self::find_in(dir)?.ok_or_else(|| {
+                eyre!(
+                    "Couldn't find a config file ({}) in {}",
+                    CONFIG_FILE_NAMES.join(", "),
+                    dir.display()
+                )
+            }),
+            None => Self::find_in_ancestors(&cross::env::current_dir()?),
+        }
+    }
+
+    fn find_in_ancestors(start: &Path) -> Result<PathBuf> {
+        for dir in start.ancestors() {
+            if let Some(path) = Self::find_in(dir)? {
+                return Ok(path);
+            }
+        }
+
+        Err(eyre!(
+            "Couldn't find a config file ({}) in {} or any of its parent directories",
+            CONFIG_FILE_NAMES.join(", "),
+            start.display()
+        ))
+    }
+
+    fn find_in(dir: &Path) -> Result<Option<PathBuf>> {
+        for name in CONFIG_FILE_NAMES {
+            let path = dir.join(name);
+            if cross::fs::modified(&path)?.is_none() {
+                continue;
+            }
+
+            // Most package.json files have nothing to do with graft
+            if name == "package.json" {
+                let package_json = cross::fs::read_to_string(&path)?;
+                let package_json: serde_json::Value = serde_json::from_str(&package_json)
+                    .map_err(|err| eyre!("Failed to parse {}: {err}", path.display()))?;
+                if package_json.get("graft").is_none() {
+                    continue;
+                }
+            }
+
+            return Ok(Some(path));
+        }
+
+        Ok(None)
+    }
+
+    /// Groups entry names into waves that can each run concurrently. An entry whose `typescript.ast`
+    /// is another entry's `schema.out.ast` comes in a later wave than that entry. Paths are compared
+    /// after resolving them against the config directory, so `./schema.graphql` matches `schema.graphql`.
+    pub fn dependency_waves(&self) -> Result<Vec<Vec<&str>>> {
+        let config_dir = cross::env::current_dir()?;
+        let resolve = |path: &PathBuf| util::normalize(&config_dir.join(path));
+
+        let schema_outputs = self
+            .generates
+            .iter()
+            .filter_map(|(name, plans)| {
+                let ast_path = plans.schema_gen_plan.as_ref()?.out.ast_path.as_ref()?;
+                Some((name.as_str(), resolve(ast_path)))
+            })
+            .collect::<Vec<_>>();
+
+        let mut dependencies = self
+            .generates
+            .iter()
+            .map(|(name, plans)| {
+                let ast = plans
+                    .typescript_gen_plan
+                    .as_ref()
+                    .map(|plan| resolve(&plan.ast));
+                let producers = schema_outputs
+                    .iter()
+                    .filter(|(producer, ast_path)| {
+                        *producer != name && ast.as_ref() == Some(ast_path)
+                    })
+                    .map(|(producer, _)| *producer)
+                    .collect::<BTreeSet<_>>();
+                (name.as_str(), producers)
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        let mut waves = vec![];
+
+        while !dependencies.is_empty() {
+            let wave = dependencies
+                .iter()
+                .filter(|(_, producers)| producers.is_empty())
+                .map(|(name, _)| *name)
+                .collect::<Vec<_>>();
+
+            if wave.is_empty() {
+                let names = dependencies.keys().copied().collect::<Vec<_>>();
+                return Err(eyre!(
+                    "generates entries depend on each other's schema output: {}",
+                    names.join(", ")
+                ));
+            }
+
+            for name in &wave {
+                dependencies.remove(name);
+            }
+            for producers in dependencies.values_mut() {
+                for name in &wave {
+                    producers.remove(name);
+                }
+            }
+
+            waves.push(wave);
+        }
+
+        Ok(waves)
+    }
 
-        let path = util::path_with_possible_prefix(dir, &PathBuf::try_from(config_name)?);
+    /// Drops the entries that `selection` leaves out, failing on any name it mentions that isn't configured
+    pub fn select(&mut self, selection: &Selection) -> Result<()> {
+        for name in selection.only.iter().chain(&selection.skip) {
+            if !self.generates.contains_key(name) {
+                return Err(eyre!("No generates entry named \"{name}\" in config"));
+            }
+        }
+
+        self.generates.retain(|name, _| selection.includes(name));
 
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
         let config_string = cross::fs::read_to_string(path)?;
 
-        Ok(serde_yaml::from_str(&config_string)?)
+        let file_name = path.file_name().and_then(std::ffi::OsStr::to_str);
+        let config = match file_name {
+            Some("package.json") => serde_json::from_str::<PackageJson>(&config_string)
+                .map_err(|err| err.to_string())
+                .and_then(|package_json| {
+                    package_json
+                        .graft
+                        .ok_or_else(|| "missing \"graft\" key".to_owned())
+                }),
+            _ => match path.extension().and_then(std::ffi::OsStr::to_str) {
+                Some("json") => serde_json::from_str(&config_string).map_err(|err| err.to_string()),
+                Some("toml") => toml::from_str(&config_string).map_err(|err| err.to_string()),
+                _ => serde_yaml::from_str(&config_string).map_err(|err| err.to_string()),
+            },
+        };
+
+        config.map_err(|err| eyre!("Failed to parse {}: {err}", path.display()))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{Config, DocumentPaths, SchemaGenPlan, TypescriptOptions};
+    use crate::app::cli::Selection;
+
+    #[test]
+    fn document_paths_from_single_pattern() {
+        let document_paths: DocumentPaths =
+            serde_yaml::from_str(r#""../../examples/app/*.graphql""#).expect("deserializing");
+
+        let resolved = document_paths.resolve(None).expect("resolving");
+
+        assert_eq!(
+            vec![
+                PathBuf::from("../../examples/app/queries.graphql"),
+                PathBuf::from("../../examples/app/fragments.graphql"),
+            ],
+            resolved
+        );
+    }
+
+    #[test]
+    fn document_paths_with_exclusion() {
+        let document_paths = DocumentPaths::from([
+            "../../examples/**/*.graphql",
+            "!../../examples/app/queries.graphql",
+        ]);
+
+        let resolved = document_paths.resolve(None).expect("resolving");
+
+        assert_eq!(
+            vec![PathBuf::from("../../examples/app/fragments.graphql")],
+            resolved
+        );
+    }
+
+    #[test]
+    fn document_pattern_without_matches() {
+        let document_paths = DocumentPaths::from(["../../examples/app/missing.graphql"]);
+
+        assert!(document_paths.resolve(None).is_err());
+    }
+
+    #[test]
+    fn schema_gen_plan_headers_interpolation() {
+        std::env::set_var("GRAFT_TEST_TOKEN", "abc123");
+
+        let schema_gen_plan: SchemaGenPlan = serde_yaml::from_str(
+            r#"
+url: "https://example.com/graphql"
+headers:
+  Authorization: "Bearer {{GRAFT_TEST_TOKEN}}"
+out: {}
+"#,
+        )
+        .expect("deserializing");
+
+        assert_eq!(
+            Some(&"Bearer abc123"),
+            schema_gen_plan.headers().get("Authorization")
+        );
+    }
+
+    #[test]
+    fn config_found_in_parent_directory() {
+        let root = std::env::temp_dir().join(format!(
+            "graft-config-discovery-test-{}",
+            std::process::id()
+        ));
+        let package_dir = root.join("packages/app");
+        std::fs::create_dir_all(&package_dir).expect("creating directories");
+        std::fs::write(
+            root.join("graft.toml"),
+            r#"
+[generates.api.typescript]
+ast = "schema.graphql"
+out = "generated.ts"
+"#,
+        )
+        .expect("writing graft.toml");
+        // A package.json without a "graft" key isn't a config file
+        std::fs::write(package_dir.join("package.json"), r#"{ "name": "app" }"#)
+            .expect("writing package.json");
+
+        let found = Config::find_in_ancestors(&package_dir).expect("finding config");
+
+        assert_eq!(root.join("graft.toml"), found);
+
+        let config = Config::load(&found).expect("loading config");
+        assert!(config.generates["api"].typescript_gen_plan.is_some());
+
+        std::fs::write(
+            package_dir.join("package.json"),
+            r#"{ "name": "app", "graft": { "generates": {} } }"#,
+        )
+        .expect("writing package.json");
+
+        assert_eq!(
+            package_dir.join("package.json"),
+            Config::find(Some(&package_dir)).expect("finding config")
+        );
+        assert!(Config::load(&package_dir.join("package.json"))
+            .expect("loading config")
+            .generates
+            .is_empty());
+
+        std::fs::remove_dir_all(&root).expect("removing directories");
+    }
+
+    #[test]
+    fn config_parse_errors_name_file_and_location() {
+        let dir =
+            std::env::temp_dir().join(format!("graft-config-error-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("creating directory");
+        let path = dir.join(".graft.json");
+        std::fs::write(&path, "{\n  \"generates\": {\n    \"api\": 5\n  }\n}\n")
+            .expect("writing .graft.json");
+
+        let err = Config::load(&path).expect_err("loading invalid config");
+        std::fs::remove_dir_all(&dir).expect("removing directory");
+
+        let message = err.to_string();
+        assert!(message.contains(&path.display().to_string()), "{message}");
+        assert!(message.contains("line 3 column"), "{message}");
+    }
+
+    #[test]
+    fn scalar_mapping_without_output_type() {
+        let err = serde_yaml::from_str::<TypescriptOptions>(
+            r#"
+scalarNewtypes:
+  DateTime:
+    input: string
+    import:
+      from: ./dates
+      name: Instant
+"#,
+        )
+        .expect_err("deserializing");
+
+        assert!(err.to_string().contains("scalar \"DateTime\""), "{err}");
+    }
+
+    #[test]
+    fn config_select_entries() {
+        let config = || -> Config {
+            serde_yaml::from_str(
+                r#"
+generates:
+  billing: {}
+  catalog: {}
+  search: {}
+"#,
+            )
+            .expect("deserializing")
+        };
+        let names = |config: &Config| {
+            let mut names = config.generates.keys().cloned().collect::<Vec<_>>();
+            names.sort();
+            names
+        };
+
+        let mut only = config();
+        only.select(&Selection {
+            only: vec!["billing".to_owned(), "catalog".to_owned()],
+            skip: vec![],
+        })
+        .expect("selecting");
+        assert_eq!(vec!["billing", "catalog"], names(&only));
+
+        let mut skip = config();
+        skip.select(&Selection {
+            only: vec![],
+            skip: vec!["catalog".to_owned()],
+        })
+        .expect("selecting");
+        assert_eq!(vec!["billing", "search"], names(&skip));
+
+        assert!(config()
+            .select(&Selection {
+                only: vec!["shipping".to_owned()],
+                skip: vec![],
+            })
+            .is_err());
+    }
+
+    #[test]
+    fn dependency_waves_wait_for_schema_outputs() {
+        let config: Config = serde_yaml::from_str(
+            r#"
+generates:
+  schema:
+    schema:
+      path: schema.json
+      out:
+        ast: shared.graphql
+  app:
+    typescript:
+      ast: ./shared.graphql
+      out: app.ts
+  admin:
+    typescript:
+      ast: shared.graphql
+      out: admin.ts
+  billing:
+    schema:
+      path: billing.json
+      out:
+        ast: billing.graphql
+    typescript:
+      ast: billing.graphql
+      out: billing.ts
+"#,
+        )
+        .expect("deserializing");
+
+        assert_eq!(
+            vec![vec!["billing", "schema"], vec!["admin", "app"]],
+            config.dependency_waves().expect("ordering entries")
+        );
     }
 }
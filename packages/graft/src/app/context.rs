@@ -1,19 +1,83 @@
+use std::cell::RefCell;
 use std::path::PathBuf;
 
-use crate::cross_println;
+use crate::{cross_eprint, cross_print};
 
+enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// Messages held back by a buffered [`Context`], in the order they were logged
+#[derive(Default)]
+pub struct Log(Vec<(Stream, String)>);
+
+impl Log {
+    fn push(&mut self, stream: Stream, msg: String) {
+        self.0.push((stream, msg));
+    }
+
+    /// Prints each message to the stream it was logged for
+    pub fn flush(self) {
+        for (stream, msg) in self.0 {
+            match stream {
+                Stream::Stdout => {
+                    cross_print!("{msg}");
+                }
+                Stream::Stderr => {
+                    cross_eprint!("{msg}");
+                }
+            }
+        }
+    }
+}
+
+#[derive(Default)]
 pub struct Context {
     pub verbose: u8,
     pub config_location: Option<PathBuf>,
+    /// Holds messages back instead of printing them, so work running concurrently can be logged in order
+    pub log: Option<RefCell<Log>>,
 }
 
 impl Context {
     pub fn print_info(&self, level: u8, msg: &str) {
         if self.verbose >= level {
-            cross_println!("{msg}");
+            self.print(&format!("{msg}\n"));
+        }
+    }
+
+    /// Prints to stdout regardless of verbosity
+    pub fn print(&self, msg: &str) {
+        match &self.log {
+            Some(log) => log.borrow_mut().push(Stream::Stdout, msg.to_owned()),
+            None => {
+                cross_print!("{msg}");
+            }
+        }
+    }
+
+    pub fn print_warning(&self, msg: &str) {
+        match &self.log {
+            Some(log) => log.borrow_mut().push(Stream::Stderr, format!("{msg}\n")),
+            None => {
+                cross_eprint!("{msg}\n");
+            }
         }
     }
 
+    pub fn buffered(&self) -> Self {
+        Self {
+            verbose: self.verbose,
+            config_location: self.config_location.clone(),
+            log: Some(RefCell::new(Log::default())),
+        }
+    }
+
+    pub fn take_log(&self) -> Log {
+        self.log.as_ref().map(|log| log.take()).unwrap_or_default()
+    }
+
     // pub fn print_error(&self, msg: &str) {
     //     cross_eprintln!("{msg}");
     // }
@@ -65,7 +65,6 @@ pub mod env {
 
     use eyre::Result;
 
-    #[cfg(feature = "debug")]
     pub fn current_dir() -> Result<std::path::PathBuf> {
         #[cfg(not(target_arch = "wasm32"))]
         {
@@ -136,7 +135,7 @@ pub mod env {
 }
 
 pub mod fs {
-    use std::path::Path;
+    use std::path::{Path, PathBuf};
 
     use eyre::Result;
 
@@ -168,15 +167,66 @@ pub mod fs {
             Ok(write!(std::fs::File::create(path)?, "{data}")?)
         }
     }
+
+    /// Milliseconds since the Unix epoch that the file was last modified, or `None` if it doesn't exist
+    pub fn modified<P: AsRef<Path>>(path: P) -> Result<Option<u128>> {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let metadata = match std::fs::metadata(path) {
+                Ok(metadata) => metadata,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+                Err(err) => return Err(err.into()),
+            };
+
+            let modified = metadata.modified()?.duration_since(std::time::UNIX_EPOCH)?;
+
+            Ok(Some(modified.as_millis()))
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let modified = super::node::modified_ms(&super::path_to_string(path)?)
+                .map_err(|err| eyre::eyre!("{err:?}"))?;
+
+            Ok(modified.map(|ms| ms as u128))
+        }
+    }
+
+    pub fn glob(pattern: &str) -> Result<Vec<PathBuf>> {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let mut paths = ::glob::glob(pattern)?.collect::<Result<Vec<_>, _>>()?;
+            // Only files match, as with `nodir` in the Node implementation
+            paths.retain(|path| path.is_file());
+            paths.sort();
+            Ok(paths)
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let mut paths = super::node::glob(pattern)
+                .map_err(|err| eyre::eyre!("{err:?}"))?
+                .into_iter()
+                .map(|path| {
+                    path.as_string()
+                        .map(PathBuf::from)
+                        .ok_or_else(|| eyre::eyre!("Failed to stringify glob match: {path:?}"))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            paths.sort();
+            Ok(paths)
+        }
+    }
 }
 
 pub mod net {
+    use std::collections::HashMap;
+
     use eyre::Result;
     use serde::Serialize;
 
     pub async fn fetch_json<B: Serialize>(
         url: &str,
         no_ssl: bool,
+        headers: &HashMap<&str, &str>,
         body: B,
     ) -> Result<serde_json::Value> {
         #[cfg(target_arch = "wasm32")]
@@ -185,13 +235,18 @@ pub mod net {
 
             let body_str = serde_json::to_string(&body)?;
 
+            let mut all_headers = serde_json::json!({
+                "Accept": "application/json",
+                "Content-Type": "application/json"
+            });
+            for (name, value) in headers {
+                all_headers[*name] = serde_json::Value::from(*value);
+            }
+
             let options = serde_json::json!({
                 "method": "POST",
                 "body": body_str,
-                "headers": {
-                    "Accept": "application/json",
-                    "Content-Type": "application/json"
-                }
+                "headers": all_headers,
             });
 
             let res = super::node::fetch_json(
@@ -211,13 +266,32 @@ pub mod net {
                 .danger_accept_invalid_certs(no_ssl)
                 .build()?;
 
-            let res = client.post(url).json(&body).send().await?;
+            let mut req = client.post(url).json(&body);
+            for (name, value) in headers {
+                req = req.header(*name, *value);
+            }
+
+            let res = req.send().await?;
 
             Ok(res.json().await?)
         }
     }
 }
 
+pub mod time {
+    pub async fn sleep(millis: u64) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            tokio::time::sleep(std::time::Duration::from_millis(millis)).await;
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            // setTimeout never rejects
+            let _ = super::node::sleep(millis as f64).await;
+        }
+    }
+}
+
 pub mod process {
     pub fn exit(code: i32) -> ! {
         #[cfg(not(target_arch = "wasm32"))]
@@ -231,3 +305,23 @@ pub mod process {
         }
     }
 }
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn glob_matches_only_files() {
+        let dir = std::env::temp_dir().join(format!("graft-glob-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("folder.graphql")).expect("creating directory");
+        std::fs::write(dir.join("file.graphql"), "").expect("writing file");
+
+        let matches = super::fs::glob(&format!("{}/*", dir.display()));
+
+        std::fs::remove_dir_all(&dir).expect("removing directory");
+
+        assert_eq!(
+            vec![dir.join("file.graphql")],
+            matches.expect("globbing directory")
+        );
+    }
+}
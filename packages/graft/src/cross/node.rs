@@ -85,4 +85,13 @@ extern "C" {
 
     #[wasm_bindgen(js_name = "readFileToString", catch)]
     pub fn read_file_to_string(path: &str) -> Result<String, JsValue>;
+
+    #[wasm_bindgen(js_name = "modifiedMs", catch)]
+    pub fn modified_ms(path: &str) -> Result<Option<f64>, JsValue>;
+
+    #[wasm_bindgen(js_name = "sleep", catch)]
+    pub async fn sleep(ms: f64) -> Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(js_name = "glob", catch)]
+    pub fn glob(pattern: &str) -> Result<Vec<JsValue>, JsValue>;
 }
@@ -1,14 +1,23 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fmt::{Display, Write as FmtWrite};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 
-use eyre::Result;
+use eyre::{eyre, Result};
 use graphql_parser::query::Document;
+use graphql_parser::Pos;
 
 use crate::app;
-use crate::app::config::{DocumentPaths, TypescriptOptions};
-use crate::debug_log;
-use crate::graphql::schema::Schema;
-use crate::typescript::{self, TypeIndex, TypescriptableWithBuffer};
+use crate::app::config::{DocumentPaths, ScalarType, TypescriptOptions};
+use crate::graphql::{
+    schema::{NamedType, Schema},
+    validation,
+};
+use crate::typescript::{self, Export, TypeIndex, TypescriptableWithBuffer};
+use crate::{cross, debug_log, util};
 
+#[derive(Default)]
 pub struct Buffer {
     pub imports: String,
     pub util_types: String,
@@ -24,13 +33,15 @@ pub struct Buffer {
     pub mutations: String,
     pub subscriptions: String,
     pub fragments: String,
+    pub zod_schemas: String,
+    pub zod_args: String,
+    pub sdk: String,
+    pub sdk_subscriptions: String,
+    pub react_hooks: String,
 }
 
-impl Display for Buffer {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut buffer_buffer = String::new();
-
-        writeln!(buffer_buffer, "{}", self.imports)?;
+impl Buffer {
+    fn write_schema_sections(&self, buffer_buffer: &mut String) -> std::fmt::Result {
         writeln!(buffer_buffer, "// Utility types")?;
         writeln!(buffer_buffer, "{}", self.util_types)?;
         writeln!(buffer_buffer, "// Scalars")?;
@@ -45,6 +56,15 @@ impl Display for Buffer {
         writeln!(buffer_buffer, "{}", self.interfaces)?;
         writeln!(buffer_buffer, "// Unions")?;
         writeln!(buffer_buffer, "{}", self.unions)?;
+        if !self.zod_schemas.is_empty() {
+            writeln!(buffer_buffer, "// Zod Schemas")?;
+            writeln!(buffer_buffer, "{}", self.zod_schemas)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_document_sections(&self, buffer_buffer: &mut String) -> std::fmt::Result {
         writeln!(buffer_buffer, "// Selection Sets")?;
         writeln!(buffer_buffer, "{}", self.selection_sets)?;
         writeln!(buffer_buffer, "// Args")?;
@@ -57,37 +77,135 @@ impl Display for Buffer {
         writeln!(buffer_buffer, "{}", self.subscriptions)?;
         writeln!(buffer_buffer, "// Fragments")?;
         write!(buffer_buffer, "{}", self.fragments)?;
+        if !self.zod_args.is_empty() {
+            writeln!(buffer_buffer, "\n// Zod Args")?;
+            write!(buffer_buffer, "{}", self.zod_args)?;
+        }
+        if !self.sdk.is_empty() {
+            writeln!(buffer_buffer, "\n// SDK")?;
+            write!(buffer_buffer, "{}", self.sdk)?;
+        }
+        if !self.react_hooks.is_empty() {
+            writeln!(buffer_buffer, "\n// React Hooks")?;
+            write!(buffer_buffer, "{}", self.react_hooks)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for Buffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut buffer_buffer = String::new();
+
+        writeln!(buffer_buffer, "{}", self.imports)?;
+        self.write_schema_sections(&mut buffer_buffer)?;
+        self.write_document_sections(&mut buffer_buffer)?;
 
         write!(f, "{buffer_buffer}")
     }
 }
 
-pub fn generate_typescript_with_document(
-    options: TypescriptOptions,
+/// Document files joined into one, along with where each file starts, so that a position in the
+/// joined document can be traced back to its file
+struct DocumentSources {
+    text: String,
+    /// The path, first line and byte range of each file
+    files: Vec<(PathBuf, usize, Range<usize>)>,
+}
+
+impl DocumentSources {
+    fn new(files: Vec<(PathBuf, String)>) -> Self {
+        let mut text = String::new();
+        let mut line = 1;
+        let files = files
+            .into_iter()
+            .map(|(path, file_text)| {
+                if !text.is_empty() {
+                    text.push('\n');
+                    line += 1;
+                }
+                let first_line = line;
+                line += file_text.matches('\n').count();
+                let start = text.len();
+                text.push_str(&file_text);
+                (path, first_line, start..text.len())
+            })
+            .collect();
+
+        Self { text, files }
+    }
+
+    /// Parses the joined document. If that fails, the first file that fails on its own is blamed, so
+    /// the error's position is within it.
+    fn parse(&self) -> Result<Document<'_, String>> {
+        graphql_parser::parse_query::<String>(&self.text).map_err(|err| {
+            self.files
+                .iter()
+                .find_map(|(path, _, range)| {
+                    graphql_parser::parse_query::<String>(&self.text[range.clone()])
+                        .err()
+                        .map(|err| eyre!("Failed to parse {}: {err}", path.display()))
+                })
+                .unwrap_or_else(|| eyre!(err))
+        })
+    }
+
+    /// `path:line:column` of a position in the joined document
+    fn locate(&self, position: Pos) -> String {
+        match self
+            .files
+            .iter()
+            .rev()
+            .find(|(_, first_line, _)| *first_line <= position.line)
+        {
+            Some((path, first_line, _)) => format!(
+                "{}:{}:{}",
+                path.display(),
+                position.line - first_line + 1,
+                position.column
+            ),
+            None => position.to_string(),
+        }
+    }
+}
+
+/// Reports the document's warnings and fails on its errors, locating them in their files if the
+/// document was joined from `sources`
+fn validate_document(
+    ctx: &app::Context,
     schema: &Schema,
-    document: Option<Document<'_, String>>,
-) -> Result<String> {
-    let mut buffer = Buffer {
-        imports: String::new(),
-        util_types: String::new(),
-        scalars: String::new(),
-        enums: String::new(),
-        objects: String::new(),
-        input_objects: String::new(),
-        interfaces: String::new(),
-        unions: String::new(),
-        selection_sets: String::new(),
-        args: String::new(),
-        queries: String::new(),
-        mutations: String::new(),
-        subscriptions: String::new(),
-        fragments: String::new(),
+    document: &Document<'_, String>,
+    sources: Option<&DocumentSources>,
+) -> Result<()> {
+    let validation = validation::validate(schema, document);
+    let describe = |error: &validation::ValidationError| match sources {
+        Some(sources) => format!("{} at {}", error.message, sources.locate(error.position)),
+        None => error.to_string(),
     };
+    for warning in &validation.warnings {
+        ctx.print_warning(&format!(
+            "{} {}",
+            console::style("Warning").yellow(),
+            describe(warning)
+        ));
+    }
+    if !validation.errors.is_empty() {
+        let errors = validation
+            .errors
+            .iter()
+            .map(describe)
+            .collect::<Vec<_>>()
+            .join("\n");
+        return Err(eyre!(
+            "Documents failed validation against the schema:\n{errors}"
+        ));
+    }
 
-    let index = TypeIndex::try_new(schema)?;
-
-    let ctx = typescript::Context { index, options };
+    Ok(())
+}
 
+fn write_document_import(ctx: &typescript::Context, buffer: &mut Buffer) -> Result<()> {
     writeln!(
         buffer.imports,
         r#"import type {{ {type_name} }} from "{package}";"#,
@@ -95,71 +213,338 @@ pub fn generate_typescript_with_document(
         package = ctx.options.document_import.package(),
     )?;
 
+    Ok(())
+}
+
+fn write_schema_types(
+    ctx: &typescript::Context,
+    schema: &Schema,
+    buffer: &mut Buffer,
+) -> Result<()> {
     writeln!(buffer.util_types, "export type Nullable<T> = T | null;")?;
+    ctx.define("Nullable", Export::Type);
     writeln!(
         buffer.util_types,
         "export type NewType<T, U> = T & {{ readonly __newtype: U }};"
     )?;
+    ctx.define("NewType", Export::Type);
 
-    if let Some(document) = document {
-        for def in document.definitions {
-            ctx.with(&def).as_typescript_on(&mut buffer)?;
-        }
+    for t in &schema.types {
+        ctx.with(t).as_typescript_on(buffer)?;
+    }
+
+    if ctx.options.sdk {
+        typescript::sdk::write_requester(ctx, buffer)?;
+    }
+
+    if ctx.options.zod {
+        write_zod_import(buffer)?;
+        typescript::zod::write_schemas(ctx, schema, buffer)?;
     }
 
+    write_scalar_imports(ctx, schema, buffer)
+}
+
+fn write_zod_import(buffer: &mut Buffer) -> Result<()> {
+    writeln!(buffer.imports, r#"import {{ z }} from "zod";"#)?;
+
+    Ok(())
+}
+
+/// Writes an `import type` line per module that the schema's scalar mappings import from
+fn write_scalar_imports(
+    ctx: &typescript::Context,
+    schema: &Schema,
+    buffer: &mut Buffer,
+) -> Result<()> {
+    let mut imports = BTreeMap::<&str, BTreeSet<&str>>::new();
     for t in &schema.types {
-        ctx.with(t).as_typescript_on(&mut buffer)?;
+        if let NamedType::Scalar { name, .. } = t {
+            if let Some(import) = ctx.options.scalar_type(name).and_then(ScalarType::import) {
+                imports
+                    .entry(&import.from)
+                    .or_default()
+                    .insert(&import.name);
+            }
+        }
+    }
+
+    for (from, names) in imports {
+        writeln!(
+            buffer.imports,
+            r#"import type {{ {} }} from "{from}";"#,
+            names.into_iter().collect::<Vec<_>>().join(", ")
+        )?;
+    }
+
+    Ok(())
+}
+
+pub fn generate_typescript_with_document(
+    app_ctx: &app::Context,
+    options: &TypescriptOptions,
+    schema: &Schema,
+    document: Option<Document<'_, String>>,
+) -> Result<String> {
+    if let Some(document) = &document {
+        validate_document(app_ctx, schema, document, None)?;
+    }
+
+    typescript_with_document(options, schema, document)
+}
+
+/// Generates typescript for a document that's already been validated
+fn typescript_with_document(
+    options: &TypescriptOptions,
+    schema: &Schema,
+    document: Option<Document<'_, String>>,
+) -> Result<String> {
+    let mut buffer = Buffer::default();
+
+    let index = TypeIndex::try_new(schema)?;
+
+    let document = document.map(Document::into_static);
+    let fragments = document
+        .as_ref()
+        .map(typescript::fragments_by_name)
+        .unwrap_or_default();
+
+    let ctx = typescript::Context {
+        index,
+        options,
+        fragments,
+        referenced: RefCell::default(),
+        defined: RefCell::default(),
+    };
+
+    write_document_import(&ctx, &mut buffer)?;
+
+    if let Some(document) = document {
+        for def in &document.definitions {
+            ctx.with(def).as_typescript_on(&mut buffer)?;
+        }
+        typescript::sdk::wrap_methods(&ctx, &mut buffer)?;
+        typescript::react_hooks::write_import(&ctx, &mut buffer)?;
     }
 
+    write_schema_types(&ctx, schema, &mut buffer)?;
+
     Ok(buffer.to_string())
 }
 
 pub fn generate_typescript(
     ctx: &app::Context,
-    options: TypescriptOptions,
-    document_paths: Option<DocumentPaths>,
+    options: &TypescriptOptions,
+    document_paths: Option<&DocumentPaths>,
     schema: &Schema,
 ) -> Result<String> {
     debug_log!("current dir files: {:?}", std::fs::read_dir("./"));
 
     let Some(document_paths) = document_paths else {
-        return generate_typescript_with_document(options, schema, None);
+        return generate_typescript_with_document(ctx, options, schema, None);
     };
 
-    let Some(full_document_string) = document_paths.resolve_to_full_document_string(ctx.config_location.as_deref())? else {
-        return generate_typescript_with_document(options, schema, None);
-    };
+    let files = document_paths.read(ctx.config_location.as_deref())?;
+    if files.is_empty() {
+        return generate_typescript_with_document(ctx, options, schema, None);
+    }
 
-    debug_log!("AST: {}", full_document_string);
+    let sources = DocumentSources::new(files);
+    debug_log!("AST: {}", sources.text);
 
-    let document = graphql_parser::parse_query::<String>(&full_document_string)?;
+    let document = sources.parse()?;
     debug_log!("Parsed document!");
 
-    generate_typescript_with_document(options, schema, Some(document))
+    validate_document(ctx, schema, &document, Some(&sources))?;
+
+    typescript_with_document(options, schema, Some(document))
+}
+
+/// Generates a module next to each document file holding only that file's operations and fragments.
+/// Schema types go in a shared module at `base_path`, which the document modules import from, as they
+/// do from each other for fragments. The documents must already have been validated.
+fn generate_near_operation_files_with_documents(
+    options: &TypescriptOptions,
+    schema: &Schema,
+    base_path: &Path,
+    documents: Vec<(PathBuf, Document<'_, String>)>,
+) -> Result<Vec<(PathBuf, String)>> {
+    let documents = documents
+        .into_iter()
+        .map(|(path, document)| (path, document.into_static()))
+        .collect::<Vec<_>>();
+
+    let full_document = Document {
+        definitions: documents
+            .iter()
+            .flat_map(|(_, document)| document.definitions.iter().cloned())
+            .collect(),
+    };
+
+    let index = TypeIndex::try_new(schema)?;
+    let ctx = typescript::Context {
+        index,
+        options,
+        fragments: typescript::fragments_by_name(&full_document),
+        referenced: RefCell::default(),
+        defined: RefCell::default(),
+    };
+
+    let mut base_buffer = Buffer::default();
+    write_schema_types(&ctx, schema, &mut base_buffer)?;
+    let mut base = String::new();
+    if !base_buffer.imports.is_empty() {
+        writeln!(base, "{}", base_buffer.imports)?;
+    }
+    base_buffer.write_schema_sections(&mut base)?;
+
+    let mut modules = HashMap::<String, (PathBuf, Export)>::new();
+    for (name, export) in ctx.take_definitions() {
+        modules.insert(name, (base_path.to_owned(), export));
+    }
+
+    // Only what each document module refers to is imported into it
+    ctx.take_references();
+
+    let mut document_buffers = vec![];
+    for (document_path, document) in documents {
+        if document.definitions.is_empty() {
+            continue;
+        }
+
+        let mut buffer = Buffer::default();
+        for def in &document.definitions {
+            ctx.with(def).as_typescript_on(&mut buffer)?;
+        }
+        typescript::sdk::wrap_methods(&ctx, &mut buffer)?;
+        let path = util::near_operation_file_path(&document_path);
+        for (name, export) in ctx.take_definitions() {
+            modules.insert(name, (path.clone(), export));
+        }
+        document_buffers.push((path, buffer, ctx.take_references()));
+    }
+
+    let mut files = vec![(base_path.to_owned(), base)];
+
+    for (path, mut buffer, used_names) in document_buffers {
+        let mut imports = BTreeMap::<&Path, Vec<String>>::new();
+        let mut value_imports = BTreeMap::<&Path, Vec<String>>::new();
+        for name in used_names {
+            match modules.get(&name) {
+                Some((module, Export::Value)) if *module != path => {
+                    value_imports.entry(module).or_default().push(name);
+                }
+                Some((module, Export::Type)) if *module != path => {
+                    imports.entry(module).or_default().push(name);
+                }
+                _ => {}
+            }
+        }
+
+        write_document_import(&ctx, &mut buffer)?;
+        typescript::react_hooks::write_import(&ctx, &mut buffer)?;
+        if !buffer.zod_args.is_empty() {
+            write_zod_import(&mut buffer)?;
+        }
+        for (module, names) in imports {
+            writeln!(
+                buffer.imports,
+                r#"import type {{ {} }} from "{}";"#,
+                names.join(", "),
+                util::relative_module_path(&path, module)?
+            )?;
+        }
+        for (module, names) in value_imports {
+            writeln!(
+                buffer.imports,
+                r#"import {{ {} }} from "{}";"#,
+                names.join(", "),
+                util::relative_module_path(&path, module)?
+            )?;
+        }
+
+        let mut module = String::new();
+        writeln!(module, "{}", buffer.imports)?;
+        buffer.write_document_sections(&mut module)?;
+        files.push((path, module));
+    }
+
+    Ok(files)
+}
+
+pub fn generate_near_operation_files(
+    ctx: &app::Context,
+    options: &TypescriptOptions,
+    document_paths: Option<&DocumentPaths>,
+    schema: &Schema,
+    base_path: &Path,
+) -> Result<Vec<(PathBuf, String)>> {
+    let mut document_paths = match document_paths {
+        Some(document_paths) => document_paths.resolve(ctx.config_location.as_deref())?,
+        None => vec![],
+    };
+    document_paths.sort();
+
+    let files = document_paths
+        .into_iter()
+        .map(|path| Ok((path.clone(), cross::fs::read_to_string(&path)?)))
+        .collect::<Result<Vec<_>>>()?;
+    let sources = DocumentSources::new(files);
+
+    // Fragments are validated against every document, since any file may spread them. They're
+    // validated joined, so every position is within the joined document and can be traced back.
+    validate_document(ctx, schema, &sources.parse()?, Some(&sources))?;
+
+    let documents = sources
+        .files
+        .iter()
+        .map(|(path, _, range)| {
+            let document = graphql_parser::parse_query::<String>(&sources.text[range.clone()])?;
+            Ok((path.clone(), document))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    generate_near_operation_files_with_documents(options, schema, base_path, documents)
 }
 
 // Native test only for now...
 #[cfg(not(target_arch = "wasm32"))]
 #[cfg(test)]
 mod test {
+    use std::path::{Path, PathBuf};
+
     use eyre::Result;
 
     use crate::{
         app::{
             self,
-            config::{DocumentImport, DocumentPaths, TypescriptOptions},
+            config::{DocumentImport, DocumentPaths, EnumStyle, MemberCase, TypescriptOptions},
+        },
+        gen::{
+            generate_near_operation_files_with_documents, generate_typescript,
+            generate_typescript_with_document, validate_document, DocumentSources,
         },
-        gen::generate_typescript,
         graphql::schema::Schema,
         introspection::Response,
     };
 
+    /// The part of `typescript` between the `start` and `end` markers
+    fn section(typescript: &str, start: &str, end: &str) -> String {
+        typescript
+            .split(start)
+            .nth(1)
+            .and_then(|rest| rest.split(end).next())
+            .expect("typescript has the section")
+            .to_owned()
+    }
+
     fn context_and_schema() -> (app::Context, Schema) {
         let schema_fetch_json = include_str!("../fixtures/star-wars-introspection-response.json");
 
         let ctx = app::Context {
             verbose: 0,
             config_location: None,
+            log: None,
         };
 
         let response: Response =
@@ -176,8 +561,8 @@ mod test {
 
         let typescript = generate_typescript(
             &ctx,
-            TypescriptOptions::default(),
-            Some(DocumentPaths::from([
+            &TypescriptOptions::default(),
+            Some(&DocumentPaths::from([
                 "../../examples/app/fragments.graphql",
                 "../../examples/app/queries.graphql",
             ])),
@@ -195,14 +580,15 @@ mod test {
 
         let typescript = generate_typescript(
             &ctx,
-            TypescriptOptions {
+            &TypescriptOptions {
                 document_import: DocumentImport::default(),
                 scalar_newtypes: None,
                 documents_hide_operation_name: true,
                 selection_set_suffix: String::new(),
                 arguments_suffix: "Variables".to_owned(),
+                ..TypescriptOptions::default()
             },
-            Some(DocumentPaths::from([
+            Some(&DocumentPaths::from([
                 "../../examples/app/fragments.graphql",
                 "../../examples/app/queries.graphql",
             ])),
@@ -213,4 +599,966 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn enum_styles() -> Result<()> {
+        let schema: Schema = graphql_parser::parse_schema::<String>(
+            r#"
+schema {
+  query: Query
+}
+
+type Query {
+  episode: Episode
+}
+
+"A film in the original trilogy"
+enum Episode {
+  NEW_HOPE
+  EMPIRE
+  "The one with the Ewoks"
+  JEDI
+}
+"#,
+        )?
+        .try_into()?;
+
+        for (enum_style, enum_member_case) in [
+            (EnumStyle::Enum, MemberCase::Pascal),
+            (EnumStyle::ConstEnum, MemberCase::Preserve),
+            (EnumStyle::Union, MemberCase::Pascal),
+            (EnumStyle::ConstObject, MemberCase::Camel),
+        ] {
+            let typescript = generate_typescript_with_document(
+                &app::Context::default(),
+                &TypescriptOptions {
+                    enum_style,
+                    enum_member_case,
+                    ..TypescriptOptions::default()
+                },
+                &schema,
+                None,
+            )?;
+
+            let enums = section(&typescript, "// Enums\n", "// Objects");
+
+            insta::assert_snapshot!(format!("enum_styles_{enum_style:?}"), enums);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn union_enum_style_without_values() -> Result<()> {
+        let schema: Schema = graphql_parser::parse_schema::<String>(
+            r#"
+type Query {
+  episode: Episode
+}
+
+enum Episode
+"#,
+        )?
+        .try_into()?;
+
+        let typescript = generate_typescript_with_document(
+            &app::Context::default(),
+            &TypescriptOptions {
+                enum_style: EnumStyle::Union,
+                ..TypescriptOptions::default()
+            },
+            &schema,
+            None,
+        )?;
+
+        assert_eq!(
+            "export type Episode = never;",
+            section(&typescript, "// Enums\n", "// Objects").trim()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn deprecated_members() -> Result<()> {
+        let schema: Schema = graphql_parser::parse_schema::<String>(
+            r#"
+schema {
+  query: Query
+}
+
+scalar String
+
+type Query {
+  user: User
+}
+
+type User {
+  "The user's full name"
+  name: String! @deprecated(reason: "Use displayName")
+  displayName: String!
+  role: Role @deprecated
+}
+
+enum Role {
+  ADMIN
+  SUPERUSER @deprecated(reason: "Use ADMIN")
+}
+"#,
+        )?
+        .try_into()?;
+
+        let sdl = graphql_parser::schema::Document::from(&schema).to_string();
+        let schema: Schema = graphql_parser::parse_schema::<String>(&sdl)?.try_into()?;
+
+        let document = graphql_parser::parse_query::<String>(
+            "query CurrentUser { user { name displayName } }",
+        )?;
+
+        let typescript = generate_typescript_with_document(
+            &app::Context::default(),
+            &TypescriptOptions::default(),
+            &schema,
+            Some(document),
+        )?;
+
+        insta::assert_snapshot!(typescript);
+
+        Ok(())
+    }
+
+    #[test]
+    fn validation_errors_name_their_file() {
+        let (ctx, schema) = context_and_schema();
+
+        let sources = DocumentSources::new(vec![
+            (
+                PathBuf::from("a.graphql"),
+                "query A {\n  allFilms {\n    totalCount\n  }\n}\n".to_owned(),
+            ),
+            (
+                PathBuf::from("b.graphql"),
+                "query B {\n  allFilms {\n    nope\n  }\n}\n".to_owned(),
+            ),
+        ]);
+        let document = sources.parse().expect("joined documents parse");
+
+        let error = validate_document(&ctx, &schema, &document, Some(&sources))
+            .expect_err("b.graphql selects a field that doesn't exist")
+            .to_string();
+        assert!(error.contains("at b.graphql:3:5"), "{error}");
+    }
+
+    #[test]
+    fn near_operation_files() -> Result<()> {
+        let (_, schema) = context_and_schema();
+
+        let documents = vec![
+            (
+                PathBuf::from("src/fragments.graphql"),
+                graphql_parser::parse_query::<String>(
+                    "fragment PersonDetails on Person { __typename name homeworld { name } }",
+                )?,
+            ),
+            (
+                PathBuf::from("src/person/queries.graphql"),
+                graphql_parser::parse_query::<String>(
+                    "query Person($personID: ID) { person(personID: $personID) { ...PersonDetails } }",
+                )?,
+            ),
+        ];
+
+        let files = generate_near_operation_files_with_documents(
+            &TypescriptOptions::default(),
+            &schema,
+            Path::new("src/types.ts"),
+            documents,
+        )?;
+
+        let paths = files
+            .iter()
+            .map(|(path, _)| path.as_path())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                Path::new("src/types.ts"),
+                Path::new("src/fragments.generated.ts"),
+                Path::new("src/person/queries.generated.ts"),
+            ],
+            paths
+        );
+
+        let (_, base) = &files[0];
+        assert!(base.contains("export type StringScalar"));
+        assert!(!base.contains("// Selection Sets"));
+
+        // `__typename: "Person"` is a string literal, not a reference to the `Person` type
+        assert!(!files[1]
+            .1
+            .lines()
+            .any(|line| line.starts_with("import") && line.contains("Person")));
+
+        insta::assert_snapshot!("near_operation_files_fragments", files[1].1);
+        insta::assert_snapshot!("near_operation_files_queries", files[2].1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn abstract_selections() -> Result<()> {
+        let schema: Schema = graphql_parser::parse_schema::<String>(
+            r#"
+schema {
+  query: Query
+}
+
+scalar String
+
+type Query {
+  node: Node
+  search: [SearchResult!]!
+}
+
+interface Node {
+  id: String!
+}
+
+type User implements Node {
+  id: String!
+  name: String!
+}
+
+type Post implements Node {
+  id: String!
+  title: String!
+  author: User!
+}
+
+union SearchResult = User | Post
+"#,
+        )?
+        .try_into()?;
+
+        let document = graphql_parser::parse_query::<String>(
+            r#"
+query Search {
+  node {
+    id
+    ... on User { name }
+    ...PostDetails
+  }
+  search {
+    __typename
+    ... on Node { id }
+    ... on Post { author { name } }
+    ... on Post { author { id } }
+  }
+}
+
+fragment PostDetails on Post {
+  title
+}
+"#,
+        )?;
+
+        let typescript = generate_typescript_with_document(
+            &app::Context::default(),
+            &TypescriptOptions::default(),
+            &schema,
+            Some(document),
+        )?;
+
+        let selection_sets = section(&typescript, "// Selection Sets\n", "// Args");
+
+        insta::assert_snapshot!(selection_sets);
+
+        Ok(())
+    }
+
+    #[test]
+    fn meta_fields() -> Result<()> {
+        let schema: Schema = graphql_parser::parse_schema::<String>(
+            r#"
+schema {
+  query: Query
+}
+
+scalar String
+
+scalar Boolean
+
+type Query {
+  user: User
+}
+
+type User {
+  name: String!
+}
+"#,
+        )?
+        .try_into()?;
+
+        let document = graphql_parser::parse_query::<String>(
+            r#"
+query Meta {
+  user {
+    __typename
+    kind: __typename
+    name
+  }
+  __schema {
+    queryType {
+      name
+      kind
+    }
+  }
+  __type(name: "User") {
+    fields {
+      name
+      isDeprecated
+    }
+  }
+}
+"#,
+        )?;
+
+        let typescript = generate_typescript_with_document(
+            &app::Context::default(),
+            &TypescriptOptions::default(),
+            &schema,
+            Some(document),
+        )?;
+
+        let selection_sets = section(&typescript, "// Selection Sets\n", "// Args");
+
+        insta::assert_snapshot!(selection_sets);
+
+        Ok(())
+    }
+
+    #[test]
+    fn documents_embed_spread_fragments_once() -> Result<()> {
+        let (_, schema) = context_and_schema();
+
+        let document = graphql_parser::parse_query::<String>(
+            r#"
+query Person {
+  person(personID: 4) {
+    ...Name
+    ...Homeworld
+  }
+}
+
+fragment Homeworld on Person {
+  ...Name
+  homeworld {
+    name
+  }
+}
+
+fragment Name on Person {
+  name
+}
+"#,
+        )?;
+
+        let typescript = generate_typescript_with_document(
+            &app::Context::default(),
+            &TypescriptOptions::default(),
+            &schema,
+            Some(document),
+        )?;
+
+        let document_definitions = |constant: &str| {
+            let line = typescript
+                .lines()
+                .find(|line| line.starts_with(&format!("export const {constant} = ")))
+                .expect("document constant exists");
+            regex_macro::regex!(r#""kind":"(?:OperationDefinition|FragmentDefinition)","(?:operation":"\w+",")?name":\{"kind":"Name","value":"(\w+)""#)
+                .captures_iter(line)
+                .map(|captures| captures[1].to_owned())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            vec!["Person", "Name", "Homeworld"],
+            document_definitions("PersonQueryDocument")
+        );
+        assert_eq!(
+            vec!["Homeworld", "Name"],
+            document_definitions("HomeworldFragmentDocument")
+        );
+        assert_eq!(vec!["Name"], document_definitions("NameFragmentDocument"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn conditional_selections() -> Result<()> {
+        let (_, schema) = context_and_schema();
+
+        let document = graphql_parser::parse_query::<String>(
+            r#"
+query Person($withHomeworld: Boolean!, $brief: Boolean!) {
+  person(personID: 4) {
+    name
+    homeworld @include(if: $withHomeworld) {
+      name
+    }
+    gender @skip(if: true)
+    birthYear @include(if: true)
+    ... @skip(if: $brief) {
+      height
+      mass
+    }
+    ... @include(if: $withHomeworld) {
+      name
+      eyeColor
+    }
+    ...Species @include(if: $withHomeworld)
+  }
+}
+
+fragment Species on Person {
+  species {
+    name
+  }
+}
+"#,
+        )?;
+
+        let typescript = generate_typescript_with_document(
+            &app::Context::default(),
+            &TypescriptOptions::default(),
+            &schema,
+            Some(document),
+        )?;
+
+        let selection_sets = section(&typescript, "// Selection Sets\n", "// Args");
+
+        insta::assert_snapshot!(selection_sets);
+
+        Ok(())
+    }
+
+    #[test]
+    fn list_nullability() -> Result<()> {
+        let schema: Schema = graphql_parser::parse_schema::<String>(
+            r#"
+schema {
+  query: Query
+}
+
+scalar String
+
+type Query {
+  lists: Lists!
+}
+
+type Lists {
+  nullableOfNullable: [String]
+  nullableOfNonNull: [String!]
+  nonNullOfNullable: [String]!
+  nonNullOfNonNull: [String!]!
+  nestedNullable: [[String]]
+  nestedNonNull: [[String!]!]!
+  nestedMixed: [[String!]]!
+  objects: [Item]!
+  nestedObjects: [[Item!]]
+}
+
+type Item {
+  name: String!
+}
+"#,
+        )?
+        .try_into()?;
+
+        let document = graphql_parser::parse_query::<String>(
+            r#"
+query Lists {
+  lists {
+    nullableOfNullable
+    nullableOfNonNull
+    nonNullOfNullable
+    nonNullOfNonNull
+    nestedNullable
+    nestedNonNull
+    nestedMixed
+    objects {
+      name
+    }
+    nestedObjects {
+      name
+    }
+  }
+}
+"#,
+        )?;
+
+        let typescript = generate_typescript_with_document(
+            &app::Context::default(),
+            &TypescriptOptions::default(),
+            &schema,
+            Some(document),
+        )?;
+
+        insta::assert_snapshot!(
+            "list_nullability_selection_sets",
+            section(&typescript, "// Selection Sets\n", "// Args")
+        );
+        insta::assert_snapshot!(
+            "list_nullability_objects",
+            section(&typescript, "// Objects\n", "// Input Objects")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn scalar_mappings() -> Result<()> {
+        let schema: Schema = graphql_parser::parse_schema::<String>(
+            r#"
+schema {
+  query: Query
+}
+
+scalar String
+
+scalar DateTime
+
+scalar Date
+
+scalar JSON
+
+type Query {
+  events(after: DateTime, range: Range): [Event!]!
+}
+
+type Event {
+  name: String!
+  at: DateTime!
+  on: Date!
+  data: JSON
+}
+
+input Range {
+  from: DateTime!
+  to: DateTime
+}
+"#,
+        )?
+        .try_into()?;
+
+        let options: TypescriptOptions = serde_yaml::from_str(
+            r#"
+scalarNewtypes:
+  JSON: unknown
+  DateTime:
+    type: Temporal.Instant
+    input: Temporal.Instant | string
+    import:
+      from: "@js-temporal/polyfill"
+      name: Temporal
+  Date:
+    type: Temporal.PlainDate
+    import:
+      from: "@js-temporal/polyfill"
+      name: Temporal
+"#,
+        )?;
+
+        let document = graphql_parser::parse_query::<String>(
+            r#"
+query Events($after: DateTime, $range: Range) {
+  events(after: $after, range: $range) {
+    at
+    on
+    data
+  }
+}
+"#,
+        )?;
+
+        let typescript = generate_typescript_with_document(
+            &app::Context::default(),
+            &options,
+            &schema,
+            Some(document),
+        )?;
+
+        insta::assert_snapshot!(typescript);
+
+        Ok(())
+    }
+
+    #[test]
+    fn default_values() -> Result<()> {
+        let schema: Schema = graphql_parser::parse_schema::<String>(
+            r#"
+schema {
+  query: Query
+}
+
+scalar String
+
+scalar Int
+
+type Query {
+  search(filter: Filter!, first: Int = 10): [String!]!
+}
+
+"How to narrow a search"
+input Filter {
+  "What to look for"
+  text: String! = "*"
+  limit: Int = 20
+  order: Order = ASC
+}
+
+enum Order {
+  ASC
+  DESC
+}
+"#,
+        )?
+        .try_into()?;
+
+        let sdl = graphql_parser::schema::Document::from(&schema).to_string();
+        assert!(sdl.contains("search(filter: Filter!, first: Int = 10): [String!]!"));
+        assert!(sdl.contains(r#"text: String! = "*""#));
+        assert!(sdl.contains("order: Order = ASC"));
+        let schema: Schema = graphql_parser::parse_schema::<String>(&sdl)?.try_into()?;
+
+        let document = graphql_parser::parse_query::<String>(
+            r#"
+query Search($filter: Filter! = { text: "rex" }, $first: Int!) {
+  search(filter: $filter, first: $first)
+}
+"#,
+        )?;
+
+        let typescript = generate_typescript_with_document(
+            &app::Context::default(),
+            &TypescriptOptions::default(),
+            &schema,
+            Some(document),
+        )?;
+
+        insta::assert_snapshot!(
+            "default_values_input_objects",
+            section(&typescript, "// Input Objects\n", "// Interfaces")
+        );
+        insta::assert_snapshot!(
+            "default_values_args",
+            section(&typescript, "// Args\n", "// Queries")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn interfaces_implementing_interfaces() -> Result<()> {
+        let schema: Schema = graphql_parser::parse_schema::<String>(
+            r#"
+schema {
+  query: Query
+}
+
+scalar String
+
+type Query {
+  entity: Entity
+}
+
+interface Entity {
+  id: String!
+}
+
+interface Node implements Entity {
+  id: String!
+  name: String!
+}
+
+type User implements Node & Entity {
+  id: String!
+  name: String!
+}
+"#,
+        )?
+        .try_into()?;
+
+        let sdl = graphql_parser::schema::Document::from(&schema).to_string();
+        assert!(sdl.contains("interface Node implements Entity {"));
+        let schema: Schema = graphql_parser::parse_schema::<String>(&sdl)?.try_into()?;
+
+        let json = serde_json::to_string(&schema)?;
+        let schema: Schema = serde_json::from_str(&json)?;
+
+        let document = graphql_parser::parse_query::<String>(
+            "query Entity { entity { id ... on Node { name } } }",
+        )?;
+
+        let typescript = generate_typescript_with_document(
+            &app::Context::default(),
+            &TypescriptOptions::default(),
+            &schema,
+            Some(document),
+        )?;
+
+        insta::assert_snapshot!(
+            "interfaces_implementing_interfaces",
+            section(&typescript, "// Interfaces\n", "// Args")
+        );
+        // The selection set types `__typename`, so the document must request it
+        assert!(section(&typescript, "// Queries\n", "// Mutations")
+            .contains(r#""name":{"kind":"Name","value":"__typename"}"#));
+
+        Ok(())
+    }
+
+    #[test]
+    fn zod_schemas() -> Result<()> {
+        let schema: Schema = graphql_parser::parse_schema::<String>(
+            r#"
+schema {
+  query: Query
+  mutation: Mutation
+}
+
+scalar String
+
+scalar Int
+
+scalar ID
+
+scalar DateTime
+
+scalar JSON
+
+enum Mood {
+  HAPPY
+  SAD
+}
+
+type Query {
+  diary(id: ID!): Diary
+}
+
+type Mutation {
+  write(entry: EntryInput!, tags: [String]): Diary
+}
+
+type Diary {
+  id: ID!
+}
+
+input EntryInput {
+  text: String!
+  mood: Mood
+  rating: Int! = 5
+  at: DateTime
+  meta: JSON
+  replies: [EntryInput!]
+}
+"#,
+        )?
+        .try_into()?;
+
+        let options: TypescriptOptions = serde_yaml::from_str(
+            r#"
+zod: true
+scalarNewtypes:
+  DateTime:
+    type: Date
+    input: Date | string
+    zod: z.union([z.date(), z.string().datetime()])
+"#,
+        )?;
+
+        let document = graphql_parser::parse_query::<String>(
+            r#"
+query Diary($id: ID!) {
+  diary(id: $id) {
+    id
+  }
+}
+
+mutation Write($entry: EntryInput!, $tags: [String]) {
+  write(entry: $entry, tags: $tags) {
+    id
+  }
+}
+
+query Nothing {
+  __typename
+}
+"#,
+        )?;
+
+        let typescript = generate_typescript_with_document(
+            &app::Context::default(),
+            &options,
+            &schema,
+            Some(document),
+        )?;
+
+        insta::assert_snapshot!(typescript);
+
+        Ok(())
+    }
+
+    #[test]
+    fn sdk() -> Result<()> {
+        let schema: Schema = graphql_parser::parse_schema::<String>(
+            r#"
+schema {
+  query: Query
+  mutation: Mutation
+  subscription: Subscription
+}
+
+scalar String
+
+scalar ID
+
+type Query {
+  hero(episode: String): Hero
+}
+
+type Mutation {
+  rename(id: ID!, name: String!): Hero
+}
+
+type Subscription {
+  heroRenamed: Hero!
+}
+
+type Hero {
+  id: ID!
+  name: String!
+}
+"#,
+        )?
+        .try_into()?;
+
+        let options: TypescriptOptions = serde_yaml::from_str("sdk: true")?;
+
+        let document = graphql_parser::parse_query::<String>(
+            r#"
+query Hero($episode: String) {
+  hero(episode: $episode) {
+    name
+  }
+}
+
+mutation Rename($id: ID!, $name: String!) {
+  rename(id: $id, name: $name) {
+    name
+  }
+}
+
+subscription HeroRenamed {
+  heroRenamed {
+    id
+    name
+  }
+}
+"#,
+        )?;
+
+        let typescript = generate_typescript_with_document(
+            &app::Context::default(),
+            &options,
+            &schema,
+            Some(document),
+        )?;
+
+        insta::assert_snapshot!(typescript);
+
+        Ok(())
+    }
+
+    #[test]
+    fn react_hooks() -> Result<()> {
+        let schema: Schema = graphql_parser::parse_schema::<String>(
+            r#"
+schema {
+  query: Query
+  mutation: Mutation
+  subscription: Subscription
+}
+
+scalar String
+
+scalar ID
+
+type Query {
+  hero(id: ID!): Hero
+  heroes: [Hero!]!
+}
+
+type Mutation {
+  rename(id: ID!, name: String!): Hero
+}
+
+type Subscription {
+  heroRenamed(id: ID): Hero!
+}
+
+type Hero {
+  id: ID!
+  name: String!
+}
+"#,
+        )?
+        .try_into()?;
+
+        let document = graphql_parser::parse_query::<String>(
+            r#"
+query Hero($id: ID!) {
+  hero(id: $id) {
+    name
+  }
+}
+
+query Heroes {
+  heroes {
+    id
+  }
+}
+
+mutation Rename($id: ID!, $name: String!) {
+  rename(id: $id, name: $name) {
+    name
+  }
+}
+
+subscription HeroRenamed($id: ID) {
+  heroRenamed(id: $id) {
+    name
+  }
+}
+"#,
+        )?;
+
+        for (library, snapshot_name) in [
+            ("\"@apollo/client\"", "react_hooks_apollo"),
+            ("urql", "react_hooks_urql"),
+        ] {
+            let options: TypescriptOptions =
+                serde_yaml::from_str(&format!("reactHooks: {library}"))?;
+
+            let typescript = generate_typescript_with_document(
+                &app::Context::default(),
+                &options,
+                &schema,
+                Some(document.clone()),
+            )?;
+
+            insta::assert_snapshot!(snapshot_name, typescript);
+        }
+
+        Ok(())
+    }
 }
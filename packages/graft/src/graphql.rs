@@ -1,4 +1,6 @@
+pub mod diff;
 #[cfg(test)]
 pub mod kind;
 pub mod query;
 pub mod schema;
+pub mod validation;
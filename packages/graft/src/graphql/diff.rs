@@ -0,0 +1,781 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use serde::Serialize;
+
+use crate::graphql::schema::{
+    Directive, Field, InputValue, NamedType, Schema, TypeRef, TypeRefContainer,
+    BUILT_IN_DIRECTIVES, BUILT_IN_SCALARS,
+};
+use crate::util::Named;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub enum Criticality {
+    /// Existing clients may stop working
+    Breaking,
+    /// Existing clients keep working, but may start seeing values they don't expect
+    Dangerous,
+    Safe,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Change {
+    pub criticality: Criticality,
+    /// Dotted path to the schema member that changed, e.g. `Query.user.id`
+    pub path: String,
+    pub message: String,
+}
+
+#[derive(Default)]
+struct Changes(Vec<Change>);
+
+impl Changes {
+    fn push(&mut self, criticality: Criticality, path: impl Into<String>, message: String) {
+        self.0.push(Change {
+            criticality,
+            path: path.into(),
+            message,
+        });
+    }
+}
+
+fn kind_name(named_type: &NamedType) -> &'static str {
+    match named_type {
+        NamedType::Scalar { .. } => "scalar",
+        NamedType::Object { .. } => "object",
+        NamedType::Interface { .. } => "interface",
+        NamedType::Union { .. } => "union",
+        NamedType::Enum { .. } => "enum",
+        NamedType::InputObject { .. } => "input object",
+    }
+}
+
+fn by_name<T, F: Fn(&T) -> &str>(items: &[T], name: F) -> HashMap<&str, &T> {
+    items.iter().map(|item| (name(item), item)).collect()
+}
+
+/// Introspection lists the built-in directives, but SDL doesn't declare them, so they're left out
+fn custom_directives(directives: &[Directive]) -> HashMap<&str, &Directive> {
+    directives
+        .iter()
+        .filter(|d| !BUILT_IN_DIRECTIVES.contains(&d.name.as_str()))
+        .map(|d| (d.name.as_str(), d))
+        .collect()
+}
+
+/// Introspection lists the built-in scalars, whereas SDL may only have the ones it refers to, so
+/// they're left out along with the introspection types
+fn custom_types(types: &[NamedType]) -> HashMap<&str, &NamedType> {
+    types
+        .iter()
+        .filter(|t| !t.is_internal() && !BUILT_IN_SCALARS.contains(&t.name()))
+        .map(|t| (t.name(), t))
+        .collect()
+}
+
+fn type_ref_eq(a: &TypeRef, b: &TypeRef) -> bool {
+    a.to_string() == b.to_string()
+}
+
+/// Whether clients reading a field can cope with its type changing from `old` to `new`
+fn is_safe_output_change(old: &TypeRef, new: &TypeRef) -> bool {
+    use TypeRefContainer::{List, NonNull};
+
+    match (old, new) {
+        (TypeRef::To { name: old }, TypeRef::To { name: new }) => old == new,
+        (TypeRef::Container(List { of_type: old }), TypeRef::Container(List { of_type: new }))
+        | (
+            TypeRef::Container(NonNull { of_type: old }),
+            TypeRef::Container(NonNull { of_type: new }),
+        ) => is_safe_output_change(old, new),
+        // Tightening nullability only removes values clients had to handle anyway
+        (
+            TypeRef::To { .. } | TypeRef::Container(List { .. }),
+            TypeRef::Container(NonNull { of_type: new }),
+        ) => is_safe_output_change(old, new),
+        _ => false,
+    }
+}
+
+/// Whether clients sending an argument or input field can cope with its type changing from `old` to `new`
+fn is_safe_input_change(old: &TypeRef, new: &TypeRef) -> bool {
+    use TypeRefContainer::{List, NonNull};
+
+    match (old, new) {
+        (TypeRef::To { name: old }, TypeRef::To { name: new }) => old == new,
+        (TypeRef::Container(List { of_type: old }), TypeRef::Container(List { of_type: new }))
+        | (
+            TypeRef::Container(NonNull { of_type: old }),
+            TypeRef::Container(NonNull { of_type: new }),
+        ) => is_safe_input_change(old, new),
+        // Loosening nullability only accepts more of what clients can send
+        (TypeRef::Container(NonNull { of_type: old }), new) => is_safe_input_change(old, new),
+        _ => false,
+    }
+}
+
+/// Compares two versions of a schema, listing every change from `old` to `new` ordered from most to least critical.
+pub fn diff(old: &Schema, new: &Schema) -> Vec<Change> {
+    let mut changes = Changes::default();
+
+    diff_root_type(
+        &mut changes,
+        "query",
+        Some(&old.query_type.name),
+        Some(&new.query_type.name),
+    );
+    diff_root_type(
+        &mut changes,
+        "mutation",
+        old.mutation_type.as_ref().map(|t| &t.name),
+        new.mutation_type.as_ref().map(|t| &t.name),
+    );
+    diff_root_type(
+        &mut changes,
+        "subscription",
+        old.subscription_type.as_ref().map(|t| &t.name),
+        new.subscription_type.as_ref().map(|t| &t.name),
+    );
+
+    let old_types = custom_types(&old.types);
+    let new_types = custom_types(&new.types);
+
+    for (name, old_type) in &old_types {
+        match new_types.get(name) {
+            None => changes.push(
+                Criticality::Breaking,
+                *name,
+                format!("Type \"{name}\" was removed"),
+            ),
+            Some(new_type) => diff_type(&mut changes, old_type, new_type),
+        }
+    }
+
+    for name in new_types.keys() {
+        if !old_types.contains_key(name) {
+            changes.push(
+                Criticality::Safe,
+                *name,
+                format!("Type \"{name}\" was added"),
+            );
+        }
+    }
+
+    let old_directives = custom_directives(&old.directives);
+    let new_directives = custom_directives(&new.directives);
+
+    for (name, old_directive) in &old_directives {
+        let path = format!("@{name}");
+        match new_directives.get(name) {
+            None => changes.push(
+                Criticality::Breaking,
+                path,
+                format!("Directive \"@{name}\" was removed"),
+            ),
+            Some(new_directive) => {
+                diff_args(
+                    &mut changes,
+                    &path,
+                    &format!("directive \"@{name}\""),
+                    &old_directive.args,
+                    &new_directive.args,
+                );
+            }
+        }
+    }
+
+    for name in new_directives.keys() {
+        if !old_directives.contains_key(name) {
+            changes.push(
+                Criticality::Safe,
+                format!("@{name}"),
+                format!("Directive \"@{name}\" was added"),
+            );
+        }
+    }
+
+    let mut changes = changes.0;
+    changes.sort();
+    changes
+}
+
+fn diff_root_type(
+    changes: &mut Changes,
+    operation: &str,
+    old: Option<&String>,
+    new: Option<&String>,
+) {
+    match (old, new) {
+        (Some(old), Some(new)) if old != new => changes.push(
+            Criticality::Breaking,
+            operation,
+            format!("Schema {operation} root type changed from \"{old}\" to \"{new}\""),
+        ),
+        (Some(old), None) => changes.push(
+            Criticality::Breaking,
+            operation,
+            format!("Schema {operation} root type \"{old}\" was removed"),
+        ),
+        (None, Some(new)) => changes.push(
+            Criticality::Safe,
+            operation,
+            format!("Schema {operation} root type \"{new}\" was added"),
+        ),
+        _ => {}
+    }
+}
+
+fn diff_type(changes: &mut Changes, old: &NamedType, new: &NamedType) {
+    let name = old.name();
+
+    match (old, new) {
+        (NamedType::Scalar { .. }, NamedType::Scalar { .. }) => {}
+        (
+            NamedType::Object {
+                fields: old_fields,
+                interfaces: old_interfaces,
+                ..
+            },
+            NamedType::Object {
+                fields: new_fields,
+                interfaces: new_interfaces,
+                ..
+            },
+        )
+        | (
+            NamedType::Interface {
+                fields: old_fields,
+                interfaces: old_interfaces,
+                ..
+            },
+            NamedType::Interface {
+                fields: new_fields,
+                interfaces: new_interfaces,
+                ..
+            },
+        ) => {
+            diff_fields(changes, name, old_fields, new_fields);
+            diff_members(
+                changes,
+                name,
+                old_interfaces,
+                new_interfaces,
+                |member| format!("\"{name}\" no longer implements interface \"{member}\""),
+                |member| format!("\"{name}\" now implements interface \"{member}\""),
+            );
+        }
+        (
+            NamedType::Union {
+                possible_types: old_members,
+                ..
+            },
+            NamedType::Union {
+                possible_types: new_members,
+                ..
+            },
+        ) => diff_members(
+            changes,
+            name,
+            old_members,
+            new_members,
+            |member| format!("Member \"{member}\" was removed from union \"{name}\""),
+            |member| format!("Member \"{member}\" was added to union \"{name}\""),
+        ),
+        (
+            NamedType::Enum {
+                enum_values: old_values,
+                ..
+            },
+            NamedType::Enum {
+                enum_values: new_values,
+                ..
+            },
+        ) => {
+            let old_values = by_name(old_values, |v| &v.name);
+            let new_values = by_name(new_values, |v| &v.name);
+
+            for (value, old_value) in &old_values {
+                let path = format!("{name}.{value}");
+                match new_values.get(value) {
+                    None => changes.push(
+                        Criticality::Breaking,
+                        path,
+                        format!("Value \"{value}\" was removed from enum \"{name}\""),
+                    ),
+                    Some(new_value) if new_value.is_deprecated && !old_value.is_deprecated => {
+                        changes.push(
+                            Criticality::Safe,
+                            path,
+                            format!("Enum value \"{name}.{value}\" was deprecated"),
+                        );
+                    }
+                    Some(_) => {}
+                }
+            }
+
+            for value in new_values.keys() {
+                if !old_values.contains_key(value) {
+                    // Clients switching exhaustively over the enum won't expect the new value
+                    changes.push(
+                        Criticality::Dangerous,
+                        format!("{name}.{value}"),
+                        format!("Value \"{value}\" was added to enum \"{name}\""),
+                    );
+                }
+            }
+        }
+        (
+            NamedType::InputObject {
+                input_fields: old_fields,
+                ..
+            },
+            NamedType::InputObject {
+                input_fields: new_fields,
+                ..
+            },
+        ) => {
+            let old_fields = by_name(old_fields, |f| &f.name);
+            let new_fields = by_name(new_fields, |f| &f.name);
+
+            for (field, old_field) in &old_fields {
+                let path = format!("{name}.{field}");
+                match new_fields.get(field) {
+                    None => changes.push(
+                        Criticality::Breaking,
+                        &path,
+                        format!("Input field \"{path}\" was removed"),
+                    ),
+                    Some(new_field) => {
+                        diff_input_type(changes, path, "Input field", old_field, new_field)
+                    }
+                }
+            }
+
+            for (field, new_field) in &new_fields {
+                if !old_fields.contains_key(field) {
+                    let path = format!("{name}.{field}");
+                    if new_field.of_type.is_non_null() && new_field.default_value.is_none() {
+                        changes.push(
+                            Criticality::Breaking,
+                            &path,
+                            format!("Required input field \"{path}\" was added"),
+                        );
+                    } else {
+                        changes.push(
+                            Criticality::Dangerous,
+                            &path,
+                            format!("Optional input field \"{path}\" was added"),
+                        );
+                    }
+                }
+            }
+        }
+        _ => changes.push(
+            Criticality::Breaking,
+            name,
+            format!(
+                "\"{name}\" changed from {} to {}",
+                kind_name(old),
+                kind_name(new)
+            ),
+        ),
+    }
+}
+
+fn diff_fields(changes: &mut Changes, type_name: &str, old: &[Field], new: &[Field]) {
+    let old_fields = by_name(old, |f| &f.name);
+    let new_fields = by_name(new, |f| &f.name);
+
+    for (name, old_field) in &old_fields {
+        let path = format!("{type_name}.{name}");
+        let Some(new_field) = new_fields.get(name) else {
+            changes.push(
+                Criticality::Breaking,
+                &path,
+                format!("Field \"{path}\" was removed"),
+            );
+            continue;
+        };
+
+        if !type_ref_eq(&old_field.of_type, &new_field.of_type) {
+            let criticality = if is_safe_output_change(&old_field.of_type, &new_field.of_type) {
+                Criticality::Safe
+            } else {
+                Criticality::Breaking
+            };
+            changes.push(
+                criticality,
+                &path,
+                format!(
+                    "Field \"{path}\" changed type from \"{}\" to \"{}\"",
+                    old_field.of_type, new_field.of_type
+                ),
+            );
+        }
+
+        if new_field.is_deprecated && !old_field.is_deprecated {
+            changes.push(
+                Criticality::Safe,
+                &path,
+                format!("Field \"{path}\" was deprecated"),
+            );
+        }
+
+        diff_args(
+            changes,
+            &path,
+            &format!("field \"{path}\""),
+            &old_field.args,
+            &new_field.args,
+        );
+    }
+
+    for name in new_fields.keys() {
+        if !old_fields.contains_key(name) {
+            let path = format!("{type_name}.{name}");
+            changes.push(
+                Criticality::Safe,
+                &path,
+                format!("Field \"{path}\" was added"),
+            );
+        }
+    }
+}
+
+fn diff_args(
+    changes: &mut Changes,
+    parent_path: &str,
+    parent: &str,
+    old: &[InputValue],
+    new: &[InputValue],
+) {
+    let old_args = by_name(old, |a| &a.name);
+    let new_args = by_name(new, |a| &a.name);
+
+    for (name, old_arg) in &old_args {
+        let path = format!("{parent_path}.{name}");
+        match new_args.get(name) {
+            None => changes.push(
+                Criticality::Breaking,
+                path,
+                format!("Argument \"{name}\" was removed from {parent}"),
+            ),
+            Some(new_arg) => diff_input_type(changes, path, "Argument", old_arg, new_arg),
+        }
+    }
+
+    for (name, new_arg) in &new_args {
+        if !old_args.contains_key(name) {
+            let path = format!("{parent_path}.{name}");
+            if new_arg.of_type.is_non_null() && new_arg.default_value.is_none() {
+                changes.push(
+                    Criticality::Breaking,
+                    path,
+                    format!("Required argument \"{name}\" was added to {parent}"),
+                );
+            } else {
+                changes.push(
+                    Criticality::Dangerous,
+                    path,
+                    format!("Optional argument \"{name}\" was added to {parent}"),
+                );
+            }
+        }
+    }
+}
+
+fn diff_input_type(
+    changes: &mut Changes,
+    path: String,
+    subject: &str,
+    old: &InputValue,
+    new: &InputValue,
+) {
+    if old.default_value != new.default_value {
+        let message = match (&old.default_value, &new.default_value) {
+            (Some(old_default), Some(new_default)) => format!(
+                "{subject} \"{path}\" changed default value from {old_default} to {new_default}"
+            ),
+            (None, Some(new_default)) => {
+                format!("{subject} \"{path}\" was given default value {new_default}")
+            }
+            (_, None) => format!("{subject} \"{path}\" no longer has a default value"),
+        };
+        changes.push(Criticality::Dangerous, path.clone(), message);
+    }
+
+    if type_ref_eq(&old.of_type, &new.of_type) {
+        return;
+    }
+
+    let criticality = if is_safe_input_change(&old.of_type, &new.of_type) {
+        Criticality::Safe
+    } else {
+        Criticality::Breaking
+    };
+    let message = format!(
+        "{subject} \"{path}\" changed type from \"{}\" to \"{}\"",
+        old.of_type, new.of_type
+    );
+    changes.push(criticality, path, message);
+}
+
+fn diff_members(
+    changes: &mut Changes,
+    type_name: &str,
+    old: &[TypeRef],
+    new: &[TypeRef],
+    removed: impl Fn(&str) -> String,
+    added: impl Fn(&str) -> String,
+) {
+    let old_members = old.iter().map(TypeRef::base_name).collect::<Vec<_>>();
+    let new_members = new.iter().map(TypeRef::base_name).collect::<Vec<_>>();
+
+    for member in &old_members {
+        if !new_members.contains(member) {
+            changes.push(Criticality::Breaking, type_name, removed(member));
+        }
+    }
+
+    for member in &new_members {
+        if !old_members.contains(member) {
+            // Clients may not handle the new possible type where they previously matched every one
+            changes.push(Criticality::Dangerous, type_name, added(member));
+        }
+    }
+}
+
+/// A human readable report of `changes`, grouped by criticality
+pub fn report(changes: &[Change]) -> String {
+    if changes.is_empty() {
+        return "No schema changes\n".to_owned();
+    }
+
+    let mut report = String::new();
+
+    for (criticality, heading) in [
+        (
+            Criticality::Breaking,
+            console::style("Breaking changes").red(),
+        ),
+        (
+            Criticality::Dangerous,
+            console::style("Dangerous changes").yellow(),
+        ),
+        (Criticality::Safe, console::style("Safe changes").green()),
+    ] {
+        let group = changes
+            .iter()
+            .filter(|change| change.criticality == criticality)
+            .collect::<Vec<_>>();
+
+        if group.is_empty() {
+            continue;
+        }
+
+        // Writing to a String can't fail
+        let _ = writeln!(report, "{heading} ({})", group.len());
+        for change in group {
+            let _ = writeln!(report, "  {}", change.message);
+        }
+    }
+
+    report
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use graphql_parser::{parse_schema, schema::Document};
+
+    use super::{Change, Criticality};
+    use crate::graphql::schema::Schema;
+    use crate::introspection::Response;
+
+    fn schema(sdl: &str) -> Schema {
+        parse_schema::<String>(sdl)
+            .expect("parsing schema")
+            .try_into()
+            .expect("converting schema")
+    }
+
+    #[test]
+    fn classifies_changes() {
+        let old = schema(
+            r#"
+schema {
+  query: Query
+}
+
+type Query {
+  user(id: ID!): User
+  users: [User]
+  legacy: String
+}
+
+type User {
+  id: ID!
+  name: String
+  role: Role!
+}
+
+enum Role {
+  ADMIN
+  GUEST
+}
+
+union Actor = User | Bot
+
+type Bot {
+  id: ID!
+}
+
+input UserFilter {
+  name: String
+  limit: Int = 10
+}
+"#,
+        );
+        let new = schema(
+            r#"
+schema {
+  query: Query
+}
+
+type Query {
+  user(id: ID, expand: Boolean): User
+  users(first: Int!): [User!]
+  bots: [Bot!]!
+}
+
+type User {
+  id: ID
+  name: String!
+  role: Role!
+}
+
+enum Role {
+  ADMIN
+  MEMBER
+}
+
+union Actor = User
+
+type Bot {
+  id: ID!
+}
+
+input UserFilter {
+  name: String
+  limit: Int = 20
+  role: Role!
+  active: Boolean! = true
+}
+"#,
+        );
+
+        let changes = super::diff(&old, &new)
+            .into_iter()
+            .map(
+                |Change {
+                     criticality,
+                     message,
+                     ..
+                 }| (criticality, message),
+            )
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                (
+                    Criticality::Breaking,
+                    r#"Member "Bot" was removed from union "Actor""#.to_owned()
+                ),
+                (
+                    Criticality::Breaking,
+                    r#"Field "Query.legacy" was removed"#.to_owned()
+                ),
+                (
+                    Criticality::Breaking,
+                    r#"Required argument "first" was added to field "Query.users""#.to_owned()
+                ),
+                (
+                    Criticality::Breaking,
+                    r#"Value "GUEST" was removed from enum "Role""#.to_owned()
+                ),
+                (
+                    Criticality::Breaking,
+                    r#"Field "User.id" changed type from "ID!" to "ID""#.to_owned()
+                ),
+                (
+                    Criticality::Breaking,
+                    r#"Required input field "UserFilter.role" was added"#.to_owned()
+                ),
+                (
+                    Criticality::Dangerous,
+                    r#"Optional argument "expand" was added to field "Query.user""#.to_owned()
+                ),
+                (
+                    Criticality::Dangerous,
+                    r#"Value "MEMBER" was added to enum "Role""#.to_owned()
+                ),
+                (
+                    Criticality::Dangerous,
+                    r#"Optional input field "UserFilter.active" was added"#.to_owned()
+                ),
+                (
+                    Criticality::Dangerous,
+                    r#"Input field "UserFilter.limit" changed default value from 10 to 20"#
+                        .to_owned()
+                ),
+                (
+                    Criticality::Safe,
+                    r#"Field "Query.bots" was added"#.to_owned()
+                ),
+                (
+                    Criticality::Safe,
+                    r#"Argument "Query.user.id" changed type from "ID!" to "ID""#.to_owned()
+                ),
+                (
+                    Criticality::Safe,
+                    r#"Field "Query.users" changed type from "[User]" to "[User!]""#.to_owned()
+                ),
+                (
+                    Criticality::Safe,
+                    r#"Field "User.name" changed type from "String" to "String!""#.to_owned()
+                ),
+            ],
+            changes
+        );
+
+        // Introspection lists the built-in directives where SDL doesn't
+        let response: Response = serde_json::from_str(include_str!(
+            "../../fixtures/star-wars-introspection-response.json"
+        ))
+        .expect("response deserialization");
+        let from_json = response.schema().expect("response must have schema");
+        let from_sdl = schema(&Document::from(&from_json).to_string());
+
+        assert_eq!(Vec::<Change>::new(), super::diff(&from_json, &from_sdl));
+        assert_eq!(Vec::<Change>::new(), super::diff(&from_sdl, &from_json));
+    }
+
+    #[test]
+    fn identical_schemas_have_no_changes() {
+        let sdl = r#"
+schema {
+  query: Query
+}
+
+type Query {
+  hello(name: String): String!
+}
+"#;
+
+        assert_eq!(
+            Vec::<Change>::new(),
+            super::diff(&schema(sdl), &schema(sdl))
+        );
+    }
+}
@@ -2,6 +2,7 @@ use serde::{de, Deserialize};
 
 use super::query::Name;
 
+#[allow(dead_code)]
 pub trait Kind: for<'de> Deserialize<'de> {}
 
 pub fn visit_map<'de, A>(invalid_kind_msg: &'static str, mut map: A) -> Result<String, A::Error>
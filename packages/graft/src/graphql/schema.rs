@@ -1,8 +1,10 @@
 mod from_document;
 mod to_document;
 
+use std::sync::OnceLock;
+
 use eyre::{eyre, Result};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
 use crate::util::{Arg, Named};
 
@@ -15,6 +17,7 @@ pub struct EnumValue {
     pub deprecation_reason: Option<String>,
 }
 
+#[allow(dead_code)]
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum TypeKind {
@@ -48,6 +51,22 @@ impl TypeRef {
     pub fn is_non_null(&self) -> bool {
         matches!(self, TypeRef::Container(TypeRefContainer::NonNull { .. }))
     }
+
+    /// The name of the type at the bottom of any list and non-null wrappers
+    pub fn base_name(&self) -> &str {
+        match self {
+            TypeRef::To { name } => name,
+            TypeRef::Container(
+                TypeRefContainer::NonNull { of_type } | TypeRefContainer::List { of_type },
+            ) => of_type.base_name(),
+        }
+    }
+}
+
+impl std::fmt::Display for TypeRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", graphql_parser::schema::Type::<&str>::from(self))
+    }
 }
 
 impl From<Arg<'_>> for TypeRef {
@@ -95,6 +114,9 @@ pub enum NamedType {
     Scalar {
         name: String,
         description: Option<String>,
+        /// New in the October 2021 spec
+        #[serde(default, rename = "specifiedByURL")]
+        specified_by_url: Option<String>,
     },
     #[serde(rename_all = "camelCase")]
     Object {
@@ -109,8 +131,9 @@ pub enum NamedType {
         description: Option<String>,
         fields: Vec<Field>,
         possible_types: Vec<TypeRef>,
-        // FIXME: this field only valid in the October 2021 GraphQL spec
-        #[serde(skip)]
+        /// Interfaces implementing interfaces is new in the October 2021 spec. Servers on earlier specs
+        /// give `null` for interface types, or leave it out entirely.
+        #[serde(default, deserialize_with = "null_as_empty")]
         interfaces: Vec<TypeRef>,
     },
     #[serde(rename_all = "camelCase")]
@@ -154,6 +177,9 @@ pub struct InputValue {
     pub description: Option<String>,
     #[serde(rename = "type")]
     pub of_type: TypeRef,
+    /// The default as a GraphQL literal, e.g. `"text"` or `{ limit: 10 }`
+    #[serde(default)]
+    pub default_value: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -189,6 +215,7 @@ pub enum DirectiveLocation {
     EnumValue,
     InputObject,
     InputFieldDefinition,
+    VariableDefinition,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -198,6 +225,26 @@ pub struct Directive {
     pub name: String,
     pub locations: Vec<DirectiveLocation>,
     pub args: Vec<InputValue>,
+    /// New in the October 2021 spec
+    #[serde(default)]
+    pub is_repeatable: bool,
+}
+
+/// Directives every schema has, which SDL leaves undeclared
+pub const BUILT_IN_DIRECTIVES: [&str; 4] = ["include", "skip", "deprecated", "specifiedBy"];
+
+/// The reason `@deprecated` gives when none is passed
+pub const DEFAULT_DEPRECATION_REASON: &str = "No longer supported";
+
+/// Scalars every schema has, which SDL may leave undeclared
+pub const BUILT_IN_SCALARS: [&str; 5] = ["String", "Int", "Float", "Boolean", "ID"];
+
+fn null_as_empty<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Ok(Option::<Vec<T>>::deserialize(deserializer)?.unwrap_or_default())
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -214,3 +261,103 @@ pub struct Schema {
     pub subscription_type: Option<RootType>,
     pub directives: Vec<Directive>,
 }
+
+/// The `__Schema`, `__Type` etc. types every schema has for introspection, whether or not they were
+/// introspected
+pub fn introspection_types() -> &'static [NamedType] {
+    static TYPES: OnceLock<Vec<NamedType>> = OnceLock::new();
+
+    TYPES.get_or_init(|| {
+        let schema: Schema =
+            graphql_parser::parse_schema::<String>(include_str!("introspection_schema.graphql"))
+                .expect("parsing the introspection schema")
+                .try_into()
+                .expect("converting the introspection schema");
+
+        schema
+            .types
+            .into_iter()
+            .filter(|t| t.name().starts_with("__"))
+            .collect()
+    })
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use graphql_parser::schema::Document;
+
+    use super::{Directive, DirectiveLocation, Schema};
+
+    #[test]
+    fn directives_and_scalars_round_trip() {
+        let sdl = r#"scalar Date @specifiedBy(url: "https://tools.ietf.org/html/rfc3339")
+
+type Query {
+  today: Date
+}
+
+scalar String
+
+"Restricts access"
+directive @auth(role: String = "user") repeatable on FIELD_DEFINITION | OBJECT
+
+schema {
+  query: Query
+}
+"#;
+
+        let document = graphql_parser::parse_schema::<String>(sdl).expect("schema must parse");
+        let schema = Schema::try_from(document).expect("schema from document");
+
+        let json = serde_json::to_string(&schema).expect("schema serialization");
+        let schema: Schema = serde_json::from_str(&json).expect("schema deserialization");
+
+        assert_eq!(sdl, Document::from(&schema).to_string());
+    }
+
+    #[test]
+    fn variable_definition_directives_left_out_of_sdl() {
+        let sdl = r#"type Query {
+  today: String
+}
+
+schema {
+  query: Query
+}
+"#;
+
+        let document = graphql_parser::parse_schema::<String>(sdl).expect("schema must parse");
+        let mut schema = Schema::try_from(document).expect("schema from document");
+        // Only an introspected schema can have these, since the parser rejects the location
+        for (name, locations) in [
+            ("onlyVariables", vec![DirectiveLocation::VariableDefinition]),
+            (
+                "alsoVariables",
+                vec![
+                    DirectiveLocation::Field,
+                    DirectiveLocation::VariableDefinition,
+                ],
+            ),
+        ] {
+            schema.directives.push(Directive {
+                description: None,
+                name: name.to_owned(),
+                locations,
+                args: vec![],
+                is_repeatable: false,
+            });
+        }
+
+        let losing_locations = schema
+            .directives_losing_sdl_locations()
+            .map(|d| d.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["onlyVariables", "alsoVariables"], losing_locations);
+
+        let written = Document::from(&schema).to_string();
+        assert!(!written.contains("@onlyVariables"));
+        assert!(written.contains("directive @alsoVariables on FIELD\n"));
+        graphql_parser::parse_schema::<String>(&written).expect("written schema must parse");
+    }
+}
@@ -1,7 +1,10 @@
+use std::collections::HashSet;
+
 use eyre::{eyre, Report, Result};
 use graphql_parser::schema as gp;
 
-use crate::graphql::schema as ac;
+use crate::graphql::schema::{self as ac, DEFAULT_DEPRECATION_REASON};
+use crate::util::Named;
 
 impl From<gp::Type<'_, String>> for ac::TypeRef {
     fn from(value: gp::Type<'_, String>) -> Self {
@@ -17,6 +20,19 @@ impl From<gp::Type<'_, String>> for ac::TypeRef {
     }
 }
 
+/// The URL given to `@specifiedBy` on a scalar
+fn specified_by_url(directives: &[gp::Directive<'_, String>]) -> Option<String> {
+    directives
+        .iter()
+        .find(|directive| directive.name == "specifiedBy")?
+        .arguments
+        .iter()
+        .find_map(|(name, value)| match (name.as_str(), value) {
+            ("url", gp::Value::String(url)) => Some(url.clone()),
+            _ => None,
+        })
+}
+
 impl From<gp::InputValue<'_, String>> for ac::InputValue {
     fn from(
         gp::InputValue {
@@ -24,7 +40,7 @@ impl From<gp::InputValue<'_, String>> for ac::InputValue {
             description,
             name,
             value_type,
-            default_value: _,
+            default_value,
             // Reading directives with introspection not supported: https://stackoverflow.com/a/65064958/2269124
             directives: _,
         }: gp::InputValue<'_, String>,
@@ -33,10 +49,32 @@ impl From<gp::InputValue<'_, String>> for ac::InputValue {
             name,
             description,
             of_type: value_type.into(),
+            default_value: default_value.map(|value| value.to_string()),
         }
     }
 }
 
+/// Whether a definition is marked `@deprecated`, and its reason, which defaults as in introspection
+fn deprecation(directives: &[gp::Directive<'_, String>]) -> (bool, Option<String>) {
+    let Some(deprecated) = directives
+        .iter()
+        .find(|directive| directive.name == "deprecated")
+    else {
+        return (false, None);
+    };
+
+    let reason = deprecated
+        .arguments
+        .iter()
+        .find_map(|(name, value)| match (name.as_str(), value) {
+            ("reason", gp::Value::String(reason)) => Some(reason.clone()),
+            _ => None,
+        })
+        .unwrap_or_else(|| DEFAULT_DEPRECATION_REASON.to_owned());
+
+    (true, Some(reason))
+}
+
 impl From<gp::Field<'_, String>> for ac::Field {
     fn from(
         gp::Field {
@@ -45,16 +83,17 @@ impl From<gp::Field<'_, String>> for ac::Field {
             name,
             arguments,
             field_type,
-            directives: _,
+            directives,
         }: gp::Field<'_, String>,
     ) -> Self {
+        let (is_deprecated, deprecation_reason) = deprecation(&directives);
         Self {
             name,
             description,
             args: arguments.into_iter().map(Into::into).collect(),
             of_type: field_type.into(),
-            is_deprecated: false,
-            deprecation_reason: None,
+            is_deprecated,
+            deprecation_reason,
         }
     }
 }
@@ -65,14 +104,15 @@ impl From<gp::EnumValue<'_, String>> for ac::EnumValue {
             position: _,
             description,
             name,
-            directives: _,
+            directives,
         }: gp::EnumValue<'_, String>,
     ) -> Self {
+        let (is_deprecated, deprecation_reason) = deprecation(&directives);
         Self {
             name,
             description,
-            is_deprecated: false,
-            deprecation_reason: None,
+            is_deprecated,
+            deprecation_reason,
         }
     }
 }
@@ -84,8 +124,12 @@ impl From<gp::TypeDefinition<'_, String>> for ac::NamedType {
                 position: _,
                 description,
                 name,
-                directives: _,
-            }) => ac::NamedType::Scalar { name, description },
+                directives,
+            }) => ac::NamedType::Scalar {
+                name,
+                description,
+                specified_by_url: specified_by_url(&directives),
+            },
             gp::TypeDefinition::Object(gp::ObjectType {
                 position: _,
                 description,
@@ -193,8 +237,7 @@ impl From<gp::DirectiveDefinition<'_, String>> for ac::Directive {
             description,
             name,
             arguments,
-            // Not supported in GraphQL 2018
-            repeatable: _,
+            repeatable,
             locations,
         }: gp::DirectiveDefinition<'_, String>,
     ) -> Self {
@@ -203,6 +246,7 @@ impl From<gp::DirectiveDefinition<'_, String>> for ac::Directive {
             name,
             locations: locations.into_iter().map(Into::into).collect(),
             args: arguments.into_iter().map(Into::into).collect(),
+            is_repeatable: repeatable,
         }
     }
 }
@@ -211,6 +255,7 @@ impl TryFrom<gp::Document<'_, String>> for ac::Schema {
     type Error = Report;
 
     fn try_from(document: gp::Document<'_, String>) -> Result<Self> {
+        let mut schema_defined = false;
         let mut query_type = Option::<ac::RootType>::None;
         let mut mutation_type = Option::<ac::RootType>::None;
         let mut subscription_type = Option::<ac::RootType>::None;
@@ -227,9 +272,10 @@ impl TryFrom<gp::Document<'_, String>> for ac::Schema {
                     mutation,
                     subscription,
                 }) => {
-                    if query_type.is_some() {
+                    if schema_defined {
                         return Err(eyre!("Tried to set schema definition more than once"));
                     }
+                    schema_defined = true;
                     query_type = query.map(|name| ac::RootType { name });
                     mutation_type = mutation.map(|name| ac::RootType { name });
                     subscription_type = subscription.map(|name| ac::RootType { name });
@@ -244,7 +290,37 @@ impl TryFrom<gp::Document<'_, String>> for ac::Schema {
             }
         }
 
-        let query_type = query_type.ok_or_else(|| eyre!("query_type was not filled"))?;
+        // Without a schema definition, the root types are the ones with the conventional names
+        if !schema_defined {
+            let root_type = |name: &str| {
+                types
+                    .iter()
+                    .any(|t| t.name() == name)
+                    .then(|| ac::RootType {
+                        name: name.to_owned(),
+                    })
+            };
+            query_type = root_type("Query");
+            mutation_type = root_type("Mutation");
+            subscription_type = root_type("Subscription");
+        }
+        let query_type = query_type.ok_or_else(|| eyre!("Schema has no query type"))?;
+
+        // SDL may leave out the built-in scalars, which every schema has
+        let referenced = referenced_type_names(&types, &directives);
+        let undeclared_scalars = ac::BUILT_IN_SCALARS
+            .into_iter()
+            .filter(|name| referenced.contains(name) && !types.iter().any(|t| t.name() == *name))
+            .collect::<Vec<_>>();
+        types.extend(
+            undeclared_scalars
+                .into_iter()
+                .map(|name| ac::NamedType::Scalar {
+                    name: name.to_owned(),
+                    description: None,
+                    specified_by_url: None,
+                }),
+        );
 
         Ok(ac::Schema {
             types,
@@ -255,3 +331,34 @@ impl TryFrom<gp::Document<'_, String>> for ac::Schema {
         })
     }
 }
+
+/// The names of the types referred to by fields, arguments and input fields
+fn referenced_type_names<'a>(
+    types: &'a [ac::NamedType],
+    directives: &'a [ac::Directive],
+) -> HashSet<&'a str> {
+    let args = |args: &'a [ac::InputValue]| args.iter().map(|arg| arg.of_type.base_name());
+    let mut referenced = directives
+        .iter()
+        .flat_map(|d| args(&d.args))
+        .collect::<HashSet<_>>();
+
+    for t in types {
+        match t {
+            ac::NamedType::Object { fields, .. } | ac::NamedType::Interface { fields, .. } => {
+                for field in fields {
+                    referenced.insert(field.of_type.base_name());
+                    referenced.extend(args(&field.args));
+                }
+            }
+            ac::NamedType::InputObject { input_fields, .. } => {
+                referenced.extend(args(input_fields));
+            }
+            ac::NamedType::Scalar { .. }
+            | ac::NamedType::Union { .. }
+            | ac::NamedType::Enum { .. } => {}
+        }
+    }
+
+    referenced
+}
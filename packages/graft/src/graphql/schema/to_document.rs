@@ -10,7 +10,10 @@ use graphql_parser::{
 
 use crate::util::MaybeNamed;
 
-use super::{EnumValue, Field, InputValue, NamedType, Schema, TypeRef, TypeRefContainer};
+use super::{
+    Directive, DirectiveLocation, EnumValue, Field, InputValue, NamedType, Schema, TypeRef,
+    TypeRefContainer, BUILT_IN_DIRECTIVES, DEFAULT_DEPRECATION_REASON,
+};
 
 impl<'a> From<&'a Schema> for Document<'a, &'a str> {
     fn from(schema: &'a Schema) -> Self {
@@ -27,6 +30,15 @@ impl<'a> From<&'a Schema> for Document<'a, &'a str> {
             .iter()
             .filter(|t| !t.is_internal())
             .map(|t| Definition::TypeDefinition(t.into()))
+            .chain(
+                schema
+                    .directives
+                    .iter()
+                    .filter(|d| !BUILT_IN_DIRECTIVES.contains(&d.name.as_str()))
+                    // A definition without locations isn't valid SDL
+                    .filter(|d| d.locations.iter().any(|l| l.to_graphql_parser().is_some()))
+                    .map(|d| Definition::DirectiveDefinition(d.into())),
+            )
             .collect();
 
         let mut doc = Document { definitions };
@@ -37,13 +49,94 @@ impl<'a> From<&'a Schema> for Document<'a, &'a str> {
     }
 }
 
+impl Schema {
+    /// The custom directives that lose locations when written to SDL, and so are written without
+    /// them, or left out if they'd have none
+    pub fn directives_losing_sdl_locations(&self) -> impl Iterator<Item = &Directive> {
+        self.directives.iter().filter(|d| {
+            !BUILT_IN_DIRECTIVES.contains(&d.name.as_str())
+                && d.locations.iter().any(|l| l.to_graphql_parser().is_none())
+        })
+    }
+}
+
+impl<'a> From<&'a Directive> for gql_parser::DirectiveDefinition<'a, &'a str> {
+    fn from(directive: &'a Directive) -> Self {
+        Self {
+            position: Pos::default(),
+            description: directive.description.clone(),
+            name: directive.name.as_str(),
+            arguments: directive.args.iter().map(Into::into).collect(),
+            repeatable: directive.is_repeatable,
+            locations: directive
+                .locations
+                .iter()
+                .filter_map(DirectiveLocation::to_graphql_parser)
+                .collect(),
+        }
+    }
+}
+
+impl DirectiveLocation {
+    /// The parser predates variable definition directives, so they can't be written to SDL
+    fn to_graphql_parser(&self) -> Option<gql_parser::DirectiveLocation> {
+        use gql_parser::DirectiveLocation as gp;
+        use DirectiveLocation as ac;
+        let location = match self {
+            ac::Query => gp::Query,
+            ac::Mutation => gp::Mutation,
+            ac::Subscription => gp::Subscription,
+            ac::Field => gp::Field,
+            ac::FragmentDefinition => gp::FragmentDefinition,
+            ac::FragmentSpread => gp::FragmentSpread,
+            ac::InlineFragment => gp::InlineFragment,
+            ac::Schema => gp::Schema,
+            ac::Scalar => gp::Scalar,
+            ac::Object => gp::Object,
+            ac::FieldDefinition => gp::FieldDefinition,
+            ac::ArgumentDefinition => gp::ArgumentDefinition,
+            ac::Interface => gp::Interface,
+            ac::Union => gp::Union,
+            ac::Enum => gp::Enum,
+            ac::EnumValue => gp::EnumValue,
+            ac::InputObject => gp::InputObject,
+            ac::InputFieldDefinition => gp::InputFieldDefinition,
+            ac::VariableDefinition => return None,
+        };
+        Some(location)
+    }
+}
+
+fn deprecated_directive(
+    is_deprecated: bool,
+    deprecation_reason: Option<&String>,
+) -> Vec<gql_parser::Directive<'_, &str>> {
+    if !is_deprecated {
+        return vec![];
+    }
+
+    vec![gql_parser::Directive {
+        position: Pos::default(),
+        name: "deprecated",
+        // Like graphql-js, leave the default reason implied
+        arguments: deprecation_reason
+            .filter(|reason| *reason != DEFAULT_DEPRECATION_REASON)
+            .map(|reason| ("reason", gql_parser::Value::String(reason.clone())))
+            .into_iter()
+            .collect(),
+    }]
+}
+
 impl<'a> From<&'a EnumValue> for gql_parser::EnumValue<'a, &'a str> {
     fn from(value: &'a EnumValue) -> Self {
         Self {
             position: Pos::default(),
             description: value.description.as_ref().cloned(),
             name: value.name.as_str(),
-            directives: vec![],
+            directives: deprecated_directive(
+                value.is_deprecated,
+                value.deprecation_reason.as_ref(),
+            ),
         }
     }
 }
@@ -51,11 +144,22 @@ impl<'a> From<&'a EnumValue> for gql_parser::EnumValue<'a, &'a str> {
 impl<'a> From<&'a NamedType> for TypeDefinition<'a, &'a str> {
     fn from(t: &'a NamedType) -> Self {
         match t {
-            NamedType::Scalar { name, description } => TypeDefinition::Scalar(ScalarType {
+            NamedType::Scalar {
+                name,
+                description,
+                specified_by_url,
+            } => TypeDefinition::Scalar(ScalarType {
                 position: Pos::default(),
                 description: description.as_ref().map(|d| d.as_str().into()),
                 name: name.as_str(),
-                directives: vec![],
+                directives: specified_by_url
+                    .iter()
+                    .map(|url| gql_parser::Directive {
+                        position: Pos::default(),
+                        name: "specifiedBy",
+                        arguments: vec![("url", gql_parser::Value::String(url.clone()))],
+                    })
+                    .collect(),
             }),
             NamedType::Object {
                 name,
@@ -137,7 +241,12 @@ impl<'a> From<&'a InputValue> for gql_parser::InputValue<'a, &'a str> {
             description: input.description.as_ref().cloned(),
             name: input.name.as_str(),
             value_type: input.of_type.borrow().into(),
-            default_value: None,
+            // A default is kept as the GraphQL literal introspection gives for it, so it's written
+            // back as is. An enum value is the one variant that prints its text unquoted and
+            // unescaped. Parsing the literal into its own `Value` isn't possible here, since the
+            // parser has no entry point for a lone value, and enum names inside it would have to
+            // borrow from a parsed document that doesn't outlive this conversion.
+            default_value: input.default_value.as_deref().map(gql_parser::Value::Enum),
             directives: vec![],
         }
     }
@@ -151,7 +260,10 @@ impl<'a> From<&'a Field> for gql_parser::Field<'a, &'a str> {
             name: field.name.as_str(),
             arguments: field.args.iter().map(Into::into).collect(),
             field_type: field.of_type.borrow().into(),
-            directives: vec![],
+            directives: deprecated_directive(
+                field.is_deprecated,
+                field.deprecation_reason.as_ref(),
+            ),
         }
     }
 }
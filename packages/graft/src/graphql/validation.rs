@@ -0,0 +1,1436 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::Display;
+
+use graphql_parser::query::{
+    Definition, Directive, Document, Field as SelectedField, FragmentDefinition,
+    OperationDefinition, Selection, SelectionSet, TypeCondition, Value, VariableDefinition,
+};
+use graphql_parser::Pos;
+
+use crate::graphql::schema::{
+    introspection_types, Field, InputValue, NamedType, Schema, TypeRef, TypeRefContainer,
+};
+use crate::util::Named;
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ValidationError {
+    pub position: Pos,
+    pub message: String,
+}
+
+/// Everything found while validating a document. Only errors stop generation.
+#[derive(Debug, Default)]
+pub struct Validation {
+    pub errors: Vec<ValidationError>,
+    pub warnings: Vec<ValidationError>,
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at {}", self.message, self.position)
+    }
+}
+
+/// Definitions that every schema has, whether or not they were introspected
+struct Builtins {
+    typename: Field,
+    schema: Field,
+    type_: Field,
+    condition_args: Vec<InputValue>,
+    scalars: Vec<NamedType>,
+}
+
+fn non_null(name: &str) -> TypeRef {
+    TypeRef::Container(TypeRefContainer::NonNull {
+        of_type: Box::new(TypeRef::To {
+            name: name.to_owned(),
+        }),
+    })
+}
+
+fn meta_field(name: &str, args: Vec<InputValue>, of_type: TypeRef) -> Field {
+    Field {
+        name: name.to_owned(),
+        description: None,
+        args,
+        of_type,
+        is_deprecated: false,
+        deprecation_reason: None,
+    }
+}
+
+fn input_value(name: &str, of_type: TypeRef) -> InputValue {
+    InputValue {
+        name: name.to_owned(),
+        description: None,
+        of_type,
+        default_value: None,
+    }
+}
+
+impl Builtins {
+    fn new() -> Self {
+        Self {
+            typename: meta_field("__typename", vec![], non_null("String")),
+            schema: meta_field("__schema", vec![], non_null("__Schema")),
+            type_: meta_field(
+                "__type",
+                vec![input_value("name", non_null("String"))],
+                TypeRef::To {
+                    name: "__Type".to_owned(),
+                },
+            ),
+            condition_args: vec![input_value("if", non_null("Boolean"))],
+            // Schemas read from SDL don't declare the built-in scalars
+            scalars: ["Int", "Float", "String", "Boolean", "ID"]
+                .into_iter()
+                .map(|name| NamedType::Scalar {
+                    name: name.to_owned(),
+                    description: None,
+                    specified_by_url: None,
+                })
+                .collect(),
+        }
+    }
+}
+
+struct VariableUsage<'d> {
+    name: &'d str,
+    location_type: TypeRef,
+    location_has_default: bool,
+    position: Pos,
+}
+
+#[derive(Default)]
+struct Usages<'d> {
+    variables: Vec<VariableUsage<'d>>,
+    fragment_spreads: Vec<(&'d str, Pos)>,
+}
+
+struct CollectedField<'a, 'd, 't> {
+    parent: &'a NamedType,
+    field: &'d SelectedField<'t, String>,
+    definition: Option<&'a Field>,
+}
+
+struct Operation<'d, 't> {
+    name: Option<&'d str>,
+    variable_definitions: &'d [VariableDefinition<'t, String>],
+    usages: Usages<'d>,
+}
+
+struct Validator<'a, 'd, 't> {
+    schema: &'a Schema,
+    builtins: &'a Builtins,
+    types: HashMap<&'a str, &'a NamedType>,
+    fragments: HashMap<&'d str, &'d FragmentDefinition<'t, String>>,
+    errors: Vec<ValidationError>,
+    warnings: Vec<ValidationError>,
+}
+
+/// Validates a document against the schema, returning every error and warning found, ordered by position.
+pub fn validate<'d, 't>(schema: &Schema, document: &'d Document<'t, String>) -> Validation {
+    let builtins = Builtins::new();
+    let mut validator = Validator {
+        schema,
+        builtins: &builtins,
+        types: builtins
+            .scalars
+            .iter()
+            .chain(introspection_types())
+            .chain(&schema.types)
+            .map(|t| (t.name(), t))
+            .collect(),
+        fragments: HashMap::new(),
+        errors: vec![],
+        warnings: vec![],
+    };
+
+    for definition in &document.definitions {
+        if let Definition::Fragment(fragment) = definition {
+            if validator
+                .fragments
+                .insert(&fragment.name, fragment)
+                .is_some()
+            {
+                validator.error(
+                    fragment.position,
+                    format!("There can be only one fragment named \"{}\"", fragment.name),
+                );
+            }
+        }
+    }
+
+    let mut operation_names = HashSet::new();
+    let mut operations = vec![];
+    let mut fragment_usages = HashMap::new();
+
+    for definition in &document.definitions {
+        match definition {
+            Definition::Operation(operation) => {
+                let (position, name, variable_definitions, directives, selection_set, root) =
+                    match operation {
+                        OperationDefinition::SelectionSet(selection_set) => (
+                            selection_set.span.0,
+                            None,
+                            &[][..],
+                            &[][..],
+                            selection_set,
+                            Some(&schema.query_type),
+                        ),
+                        OperationDefinition::Query(query) => (
+                            query.position,
+                            query.name.as_deref(),
+                            &query.variable_definitions[..],
+                            &query.directives[..],
+                            &query.selection_set,
+                            Some(&schema.query_type),
+                        ),
+                        OperationDefinition::Mutation(mutation) => (
+                            mutation.position,
+                            mutation.name.as_deref(),
+                            &mutation.variable_definitions[..],
+                            &mutation.directives[..],
+                            &mutation.selection_set,
+                            schema.mutation_type.as_ref(),
+                        ),
+                        OperationDefinition::Subscription(subscription) => (
+                            subscription.position,
+                            subscription.name.as_deref(),
+                            &subscription.variable_definitions[..],
+                            &subscription.directives[..],
+                            &subscription.selection_set,
+                            schema.subscription_type.as_ref(),
+                        ),
+                    };
+
+                if let Some(name) = name {
+                    if !operation_names.insert(name) {
+                        validator.error(
+                            position,
+                            format!("There can be only one operation named \"{name}\""),
+                        );
+                    }
+                }
+
+                let Some(root) =
+                    root.and_then(|root| validator.types.get(root.name.as_str()).copied())
+                else {
+                    validator.error(
+                        position,
+                        "Schema does not define a root type for this kind of operation".to_owned(),
+                    );
+                    continue;
+                };
+
+                let mut usages = Usages::default();
+                validator.validate_directives(directives, &mut usages);
+                validator.validate_selection_set(root, selection_set, &mut usages);
+                operations.push(Operation {
+                    name,
+                    variable_definitions,
+                    usages,
+                });
+            }
+            Definition::Fragment(fragment) => {
+                let mut usages = Usages::default();
+                validator.validate_directives(&fragment.directives, &mut usages);
+                let TypeCondition::On(type_name) = &fragment.type_condition;
+                if let Some(fragment_type) = validator.composite_type(
+                    type_name,
+                    fragment.position,
+                    &format!("Fragment \"{}\"", fragment.name),
+                ) {
+                    validator.validate_selection_set(
+                        fragment_type,
+                        &fragment.selection_set,
+                        &mut usages,
+                    );
+                }
+                fragment_usages.insert(fragment.name.as_str(), usages);
+            }
+        }
+    }
+
+    validator.validate_fragment_cycles(&fragment_usages);
+
+    for operation in &operations {
+        validator.validate_operation_variables(operation, &fragment_usages);
+    }
+
+    let mut errors = validator.errors;
+    errors.sort();
+    errors.dedup();
+
+    let mut warnings = validator.warnings;
+    warnings.sort();
+    warnings.dedup();
+
+    Validation { errors, warnings }
+}
+
+impl<'a, 'd, 't> Validator<'a, 'd, 't> {
+    fn error(&mut self, position: Pos, message: String) {
+        self.errors.push(ValidationError { position, message });
+    }
+
+    fn field_definition(&self, parent: &'a NamedType, name: &str) -> Option<&'a Field> {
+        match (name, parent) {
+            (
+                "__typename",
+                NamedType::Object { .. } | NamedType::Interface { .. } | NamedType::Union { .. },
+            ) => Some(&self.builtins.typename),
+            ("__schema", _) if parent.name() == self.schema.query_type.name => {
+                Some(&self.builtins.schema)
+            }
+            ("__type", _) if parent.name() == self.schema.query_type.name => {
+                Some(&self.builtins.type_)
+            }
+            (_, NamedType::Object { fields, .. } | NamedType::Interface { fields, .. }) => {
+                fields.iter().find(|field| field.name == name)
+            }
+            _ => None,
+        }
+    }
+
+    fn directive_args(&self, name: &str) -> Option<&'a [InputValue]> {
+        let directive = self
+            .schema
+            .directives
+            .iter()
+            .find(|directive| directive.name == name);
+
+        match (directive, name) {
+            (Some(directive), _) => Some(&directive.args),
+            (None, "include" | "skip") => Some(&self.builtins.condition_args),
+            // Schemas read from SDL don't carry directive definitions, so unknown directives are let through
+            (None, _) => None,
+        }
+    }
+
+    fn composite_type(
+        &mut self,
+        type_name: &str,
+        position: Pos,
+        subject: &str,
+    ) -> Option<&'a NamedType> {
+        match self.types.get(type_name).copied() {
+            None => {
+                self.error(position, format!("Unknown type \"{type_name}\""));
+                None
+            }
+            Some(
+                named_type @ (NamedType::Object { .. }
+                | NamedType::Interface { .. }
+                | NamedType::Union { .. }),
+            ) => Some(named_type),
+            Some(_) => {
+                self.error(
+                    position,
+                    format!("{subject} cannot condition on non composite type \"{type_name}\""),
+                );
+                None
+            }
+        }
+    }
+
+    fn possible_types(&self, named_type: &'a NamedType) -> BTreeSet<&'a str> {
+        match named_type {
+            NamedType::Object { name, .. } => BTreeSet::from([name.as_str()]),
+            NamedType::Union { possible_types, .. } => {
+                possible_types.iter().map(TypeRef::base_name).collect()
+            }
+            // Interfaces parsed from SDL don't list their possible types, so implementations are looked up too
+            NamedType::Interface {
+                name,
+                possible_types,
+                ..
+            } => possible_types
+                .iter()
+                .map(TypeRef::base_name)
+                .chain(self.schema.types.iter().filter_map(|t| match t {
+                    NamedType::Object {
+                        name: object_name,
+                        interfaces,
+                        ..
+                    } if interfaces.iter().any(|i| i.base_name() == name) => {
+                        Some(object_name.as_str())
+                    }
+                    _ => None,
+                }))
+                .collect(),
+            _ => BTreeSet::new(),
+        }
+    }
+
+    fn types_overlap(&self, a: &'a NamedType, b: &'a NamedType) -> bool {
+        !self.possible_types(a).is_disjoint(&self.possible_types(b))
+    }
+
+    fn validate_selection_set(
+        &mut self,
+        parent: &'a NamedType,
+        selection_set: &'d SelectionSet<'t, String>,
+        usages: &mut Usages<'d>,
+    ) {
+        for selection in &selection_set.items {
+            match selection {
+                Selection::Field(field) => self.validate_field(parent, field, usages),
+                Selection::FragmentSpread(spread) => {
+                    self.validate_directives(&spread.directives, usages);
+                    usages
+                        .fragment_spreads
+                        .push((&spread.fragment_name, spread.position));
+
+                    let Some(fragment) = self.fragments.get(spread.fragment_name.as_str()) else {
+                        self.error(
+                            spread.position,
+                            format!("Unknown fragment \"{}\"", spread.fragment_name),
+                        );
+                        continue;
+                    };
+
+                    let TypeCondition::On(type_name) = &fragment.type_condition;
+                    if let Some(fragment_type) = self.types.get(type_name.as_str()).copied() {
+                        if !self.types_overlap(parent, fragment_type) {
+                            self.error(
+                                spread.position,
+                                format!(
+                                    "Fragment \"{}\" cannot be spread here as objects of type \"{}\" can never be of type \"{type_name}\"",
+                                    spread.fragment_name,
+                                    parent.name(),
+                                ),
+                            );
+                        }
+                    }
+                }
+                Selection::InlineFragment(inline_fragment) => {
+                    self.validate_directives(&inline_fragment.directives, usages);
+
+                    let fragment_type = match &inline_fragment.type_condition {
+                        None => parent,
+                        Some(TypeCondition::On(type_name)) => {
+                            let Some(fragment_type) = self.composite_type(
+                                type_name,
+                                inline_fragment.position,
+                                "Fragment",
+                            ) else {
+                                continue;
+                            };
+                            if !self.types_overlap(parent, fragment_type) {
+                                self.error(
+                                    inline_fragment.position,
+                                    format!(
+                                        "Fragment cannot be spread here as objects of type \"{}\" can never be of type \"{type_name}\"",
+                                        parent.name(),
+                                    ),
+                                );
+                            }
+                            fragment_type
+                        }
+                    };
+
+                    self.validate_selection_set(
+                        fragment_type,
+                        &inline_fragment.selection_set,
+                        usages,
+                    );
+                }
+            }
+        }
+
+        self.validate_fields_can_merge(parent, selection_set);
+    }
+
+    fn validate_field(
+        &mut self,
+        parent: &'a NamedType,
+        field: &'d SelectedField<'t, String>,
+        usages: &mut Usages<'d>,
+    ) {
+        self.validate_directives(&field.directives, usages);
+
+        let Some(definition) = self.field_definition(parent, &field.name) else {
+            self.error(
+                field.position,
+                format!(
+                    "Cannot query field \"{}\" on type \"{}\"",
+                    field.name,
+                    parent.name()
+                ),
+            );
+            return;
+        };
+
+        if definition.is_deprecated {
+            let reason = definition
+                .deprecation_reason
+                .as_ref()
+                .map(|reason| format!(": {reason}"))
+                .unwrap_or_default();
+            self.warnings.push(ValidationError {
+                position: field.position,
+                message: format!(
+                    "Field \"{}.{}\" is deprecated{reason}",
+                    parent.name(),
+                    field.name
+                ),
+            });
+        }
+
+        self.validate_arguments(
+            &definition.args,
+            &field.arguments,
+            field.position,
+            &format!("field \"{}.{}\"", parent.name(), field.name),
+            usages,
+        );
+
+        // A type missing from the schema is the schema's problem, not the document's
+        let Some(field_type) = self.types.get(definition.of_type.base_name()).copied() else {
+            return;
+        };
+
+        match field_type {
+            NamedType::Scalar { .. } | NamedType::Enum { .. } => {
+                if !field.selection_set.items.is_empty() {
+                    self.error(
+                        field.position,
+                        format!(
+                            "Field \"{}\" must not have a selection since type \"{}\" has no subfields",
+                            field.name, definition.of_type
+                        ),
+                    );
+                }
+            }
+            NamedType::Object { .. } | NamedType::Interface { .. } | NamedType::Union { .. } => {
+                if field.selection_set.items.is_empty() {
+                    self.error(
+                        field.position,
+                        format!(
+                            "Field \"{}\" of type \"{}\" must have a selection of subfields",
+                            field.name, definition.of_type
+                        ),
+                    );
+                } else {
+                    self.validate_selection_set(field_type, &field.selection_set, usages);
+                }
+            }
+            NamedType::InputObject { .. } => {}
+        }
+    }
+
+    fn validate_directives(
+        &mut self,
+        directives: &'d [Directive<'t, String>],
+        usages: &mut Usages<'d>,
+    ) {
+        for directive in directives {
+            if let Some(args) = self.directive_args(&directive.name) {
+                self.validate_arguments(
+                    args,
+                    &directive.arguments,
+                    directive.position,
+                    &format!("directive \"@{}\"", directive.name),
+                    usages,
+                );
+            }
+        }
+    }
+
+    fn validate_arguments(
+        &mut self,
+        definitions: &'a [InputValue],
+        arguments: &'d [(String, Value<'t, String>)],
+        position: Pos,
+        subject: &str,
+        usages: &mut Usages<'d>,
+    ) {
+        let mut seen = HashSet::new();
+
+        for (name, value) in arguments {
+            if !seen.insert(name) {
+                self.error(
+                    position,
+                    format!("There can be only one argument named \"{name}\" on {subject}"),
+                );
+                continue;
+            }
+
+            let Some(definition) = definitions
+                .iter()
+                .find(|definition| definition.name == *name)
+            else {
+                self.error(
+                    position,
+                    format!("Unknown argument \"{name}\" on {subject}"),
+                );
+                continue;
+            };
+
+            if !self.is_valid_value(
+                value,
+                &definition.of_type,
+                definition.default_value.is_some(),
+                position,
+                usages,
+            ) {
+                self.error(
+                    position,
+                    format!(
+                        "Argument \"{name}\" on {subject} has invalid value {value}. Expected type \"{}\"",
+                        definition.of_type
+                    ),
+                );
+            }
+        }
+
+        for definition in definitions {
+            if definition.of_type.is_non_null()
+                && definition.default_value.is_none()
+                && !seen.contains(&definition.name)
+            {
+                self.error(
+                    position,
+                    format!(
+                        "Argument \"{}\" of type \"{}\" is required on {subject}, but it was not provided",
+                        definition.name, definition.of_type
+                    ),
+                );
+            }
+        }
+    }
+
+    /// Checks a literal against its expected input type, recording any variables it uses along the way
+    fn is_valid_value(
+        &self,
+        value: &'d Value<'t, String>,
+        expected: &TypeRef,
+        location_has_default: bool,
+        position: Pos,
+        usages: &mut Usages<'d>,
+    ) -> bool {
+        if let Value::Variable(name) = value {
+            usages.variables.push(VariableUsage {
+                name,
+                location_type: expected.clone(),
+                location_has_default,
+                position,
+            });
+            return true;
+        }
+
+        match expected {
+            TypeRef::Container(TypeRefContainer::NonNull { of_type }) => {
+                !matches!(value, Value::Null)
+                    && self.is_valid_value(value, of_type, false, position, usages)
+            }
+            _ if matches!(value, Value::Null) => true,
+            TypeRef::Container(TypeRefContainer::List { of_type }) => match value {
+                Value::List(items) => {
+                    // Every item is checked so that all of their variables are recorded
+                    let mut valid = true;
+                    for item in items {
+                        valid &= self.is_valid_value(item, of_type, false, position, usages);
+                    }
+                    valid
+                }
+                // Input coercion accepts a single item in place of a list
+                item => self.is_valid_value(item, of_type, false, position, usages),
+            },
+            TypeRef::To { name } => match self.types.get(name.as_str()) {
+                None => true,
+                Some(NamedType::Scalar { name, .. }) => match (name.as_str(), value) {
+                    ("Int", Value::Int(number)) => number
+                        .as_i64()
+                        .is_some_and(|number| i32::try_from(number).is_ok()),
+                    ("Float", Value::Int(_) | Value::Float(_))
+                    | ("String", Value::String(_))
+                    | ("Boolean", Value::Boolean(_))
+                    | ("ID", Value::String(_) | Value::Int(_)) => true,
+                    ("Int" | "Float" | "String" | "Boolean" | "ID", _) => false,
+                    // Custom scalars can accept any literal
+                    _ => true,
+                },
+                Some(NamedType::Enum { enum_values, .. }) => matches!(
+                    value,
+                    Value::Enum(variant) if enum_values.iter().any(|enum_value| enum_value.name == *variant)
+                ),
+                Some(NamedType::InputObject { input_fields, .. }) => {
+                    let Value::Object(fields) = value else {
+                        return false;
+                    };
+
+                    let mut valid = fields
+                        .keys()
+                        .all(|key| input_fields.iter().any(|field| field.name == *key));
+
+                    for input_field in input_fields {
+                        match fields.get(&input_field.name) {
+                            Some(field_value) => {
+                                valid = self.is_valid_value(
+                                    field_value,
+                                    &input_field.of_type,
+                                    input_field.default_value.is_some(),
+                                    position,
+                                    usages,
+                                ) && valid;
+                            }
+                            None => {
+                                valid = valid
+                                    && (!input_field.of_type.is_non_null()
+                                        || input_field.default_value.is_some());
+                            }
+                        }
+                    }
+
+                    valid
+                }
+                Some(_) => false,
+            },
+        }
+    }
+
+    fn validate_operation_variables(
+        &mut self,
+        operation: &Operation<'d, 't>,
+        fragment_usages: &HashMap<&'d str, Usages<'d>>,
+    ) {
+        let mut variable_usages = operation.usages.variables.iter().collect::<Vec<_>>();
+        let mut visited = HashSet::new();
+        let mut unvisited = operation
+            .usages
+            .fragment_spreads
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>();
+        while let Some(fragment_name) = unvisited.pop() {
+            if !visited.insert(fragment_name) {
+                continue;
+            }
+            if let Some(usages) = fragment_usages.get(fragment_name) {
+                variable_usages.extend(usages.variables.iter());
+                unvisited.extend(usages.fragment_spreads.iter().map(|(name, _)| *name));
+            }
+        }
+
+        let by_operation = operation
+            .name
+            .map(|name| format!(" by operation \"{name}\""))
+            .unwrap_or_default();
+        let in_operation = operation
+            .name
+            .map(|name| format!(" in operation \"{name}\""))
+            .unwrap_or_default();
+
+        let mut definitions = HashMap::new();
+        for definition in operation.variable_definitions {
+            if definitions
+                .insert(definition.name.as_str(), definition)
+                .is_some()
+            {
+                self.error(
+                    definition.position,
+                    format!(
+                        "There can be only one variable named \"${}\"",
+                        definition.name
+                    ),
+                );
+            }
+
+            let variable_type = TypeRef::from(definition.var_type.clone());
+            match self.types.get(variable_type.base_name()) {
+                Some(
+                    NamedType::Scalar { .. }
+                    | NamedType::Enum { .. }
+                    | NamedType::InputObject { .. },
+                ) => {}
+                Some(_) => self.error(
+                    definition.position,
+                    format!(
+                        "Variable \"${}\" cannot be non-input type \"{variable_type}\"",
+                        definition.name
+                    ),
+                ),
+                None => self.error(
+                    definition.position,
+                    format!("Unknown type \"{}\"", variable_type.base_name()),
+                ),
+            }
+
+            if let Some(default_value) = &definition.default_value {
+                let mut default_usages = Usages::default();
+                if !self.is_valid_value(
+                    default_value,
+                    &variable_type,
+                    false,
+                    definition.position,
+                    &mut default_usages,
+                ) || !default_usages.variables.is_empty()
+                {
+                    self.error(
+                        definition.position,
+                        format!(
+                            "Variable \"${}\" has invalid default value {default_value}. Expected type \"{variable_type}\"",
+                            definition.name
+                        ),
+                    );
+                }
+            }
+        }
+
+        for usage in &variable_usages {
+            let Some(definition) = definitions.get(usage.name) else {
+                self.error(
+                    usage.position,
+                    format!("Variable \"${}\" is not defined{by_operation}", usage.name),
+                );
+                continue;
+            };
+
+            let variable_type = TypeRef::from(definition.var_type.clone());
+            let variable_has_default = definition
+                .default_value
+                .as_ref()
+                .is_some_and(|value| !matches!(value, Value::Null));
+
+            if !is_variable_usage_allowed(
+                &variable_type,
+                variable_has_default,
+                &usage.location_type,
+                usage.location_has_default,
+            ) {
+                self.error(
+                    usage.position,
+                    format!(
+                        "Variable \"${}\" of type \"{variable_type}\" used in position expecting type \"{}\"",
+                        usage.name, usage.location_type
+                    ),
+                );
+            }
+        }
+
+        for definition in operation.variable_definitions {
+            if !variable_usages
+                .iter()
+                .any(|usage| usage.name == definition.name)
+            {
+                self.error(
+                    definition.position,
+                    format!(
+                        "Variable \"${}\" is never used{in_operation}",
+                        definition.name
+                    ),
+                );
+            }
+        }
+    }
+
+    fn validate_fragment_cycles(&mut self, fragment_usages: &HashMap<&'d str, Usages<'d>>) {
+        fn detect<'d>(
+            fragment_name: &'d str,
+            fragment_usages: &HashMap<&'d str, Usages<'d>>,
+            visited: &mut HashSet<&'d str>,
+            spread_path: &mut Vec<(&'d str, Pos)>,
+            path_index: &mut HashMap<&'d str, usize>,
+            errors: &mut Vec<ValidationError>,
+        ) {
+            if !visited.insert(fragment_name) {
+                return;
+            }
+            let Some(usages) = fragment_usages.get(fragment_name) else {
+                return;
+            };
+
+            path_index.insert(fragment_name, spread_path.len());
+
+            for &(spread_name, position) in &usages.fragment_spreads {
+                spread_path.push((spread_name, position));
+                match path_index.get(spread_name) {
+                    None => detect(
+                        spread_name,
+                        fragment_usages,
+                        visited,
+                        spread_path,
+                        path_index,
+                        errors,
+                    ),
+                    Some(&cycle_start) => {
+                        let via = spread_path[cycle_start..spread_path.len() - 1]
+                            .iter()
+                            .map(|(name, _)| format!("\"{name}\""))
+                            .collect::<Vec<_>>();
+                        let via = if via.is_empty() {
+                            String::new()
+                        } else {
+                            format!(" via {}", via.join(", "))
+                        };
+                        errors.push(ValidationError {
+                            position,
+                            message: format!(
+                                "Cannot spread fragment \"{spread_name}\" within itself{via}"
+                            ),
+                        });
+                    }
+                }
+                spread_path.pop();
+            }
+
+            path_index.remove(fragment_name);
+        }
+
+        let mut visited = HashSet::new();
+        let mut fragment_names = fragment_usages.keys().copied().collect::<Vec<_>>();
+        fragment_names.sort_unstable();
+        for fragment_name in fragment_names {
+            detect(
+                fragment_name,
+                fragment_usages,
+                &mut visited,
+                &mut vec![],
+                &mut HashMap::new(),
+                &mut self.errors,
+            );
+        }
+    }
+
+    fn collect_fields(
+        &self,
+        parent: &'a NamedType,
+        selection_set: &'d SelectionSet<'t, String>,
+        fields: &mut BTreeMap<&'d str, Vec<CollectedField<'a, 'd, 't>>>,
+        visited_fragments: &mut HashSet<&'d str>,
+    ) {
+        for selection in &selection_set.items {
+            match selection {
+                Selection::Field(field) => {
+                    let response_name = field.alias.as_ref().unwrap_or(&field.name);
+                    fields
+                        .entry(response_name)
+                        .or_default()
+                        .push(CollectedField {
+                            parent,
+                            field,
+                            definition: self.field_definition(parent, &field.name),
+                        });
+                }
+                Selection::InlineFragment(inline_fragment) => {
+                    let fragment_type = match &inline_fragment.type_condition {
+                        None => parent,
+                        Some(TypeCondition::On(type_name)) => {
+                            match self.types.get(type_name.as_str()) {
+                                Some(fragment_type) => fragment_type,
+                                None => continue,
+                            }
+                        }
+                    };
+                    self.collect_fields(
+                        fragment_type,
+                        &inline_fragment.selection_set,
+                        fields,
+                        visited_fragments,
+                    );
+                }
+                Selection::FragmentSpread(spread) => {
+                    if !visited_fragments.insert(&spread.fragment_name) {
+                        continue;
+                    }
+                    let Some(fragment) = self.fragments.get(spread.fragment_name.as_str()) else {
+                        continue;
+                    };
+                    let TypeCondition::On(type_name) = &fragment.type_condition;
+                    let Some(fragment_type) = self.types.get(type_name.as_str()) else {
+                        continue;
+                    };
+                    self.collect_fields(
+                        fragment_type,
+                        &fragment.selection_set,
+                        fields,
+                        visited_fragments,
+                    );
+                }
+            }
+        }
+    }
+
+    fn validate_fields_can_merge(
+        &mut self,
+        parent: &'a NamedType,
+        selection_set: &'d SelectionSet<'t, String>,
+    ) {
+        let mut fields = BTreeMap::new();
+        self.collect_fields(parent, selection_set, &mut fields, &mut HashSet::new());
+
+        for (response_name, fields) in &fields {
+            for (i, a) in fields.iter().enumerate() {
+                for b in &fields[i + 1..] {
+                    if let Some(reason) = self.find_conflict(a, b, false) {
+                        self.errors.push(ValidationError {
+                            position: b.field.position,
+                            message: format!(
+                                "Fields \"{response_name}\" conflict because {reason}. Use different aliases on the fields to fetch both if this was intentional"
+                            ),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    /// Finds why two fields with the same response name can't be merged, if they can't
+    fn find_conflict(
+        &self,
+        a: &CollectedField<'a, 'd, 't>,
+        b: &CollectedField<'a, 'd, 't>,
+        parents_mutually_exclusive: bool,
+    ) -> Option<String> {
+        // Fields on two different object types can never both apply, so may differ in name and arguments
+        let mutually_exclusive = parents_mutually_exclusive
+            || (a.parent.name() != b.parent.name()
+                && matches!(a.parent, NamedType::Object { .. })
+                && matches!(b.parent, NamedType::Object { .. }));
+
+        if !mutually_exclusive {
+            if a.field.name != b.field.name {
+                return Some(format!(
+                    "\"{}\" and \"{}\" are different fields",
+                    a.field.name, b.field.name
+                ));
+            }
+
+            let same_arguments = a.field.arguments.len() == b.field.arguments.len()
+                && a.field.arguments.iter().all(|a_argument| {
+                    b.field
+                        .arguments
+                        .iter()
+                        .any(|b_argument| a_argument == b_argument)
+                });
+            if !same_arguments {
+                return Some("they have differing arguments".to_owned());
+            }
+        }
+
+        let (Some(a_definition), Some(b_definition)) = (a.definition, b.definition) else {
+            return None;
+        };
+
+        if self.do_types_conflict(&a_definition.of_type, &b_definition.of_type) {
+            return Some(format!(
+                "they return conflicting types \"{}\" and \"{}\"",
+                a_definition.of_type, b_definition.of_type
+            ));
+        }
+
+        let (Some(a_type), Some(b_type)) = (
+            self.types.get(a_definition.of_type.base_name()),
+            self.types.get(b_definition.of_type.base_name()),
+        ) else {
+            return None;
+        };
+
+        let mut a_subfields = BTreeMap::new();
+        self.collect_fields(
+            a_type,
+            &a.field.selection_set,
+            &mut a_subfields,
+            &mut HashSet::new(),
+        );
+        let mut b_subfields = BTreeMap::new();
+        self.collect_fields(
+            b_type,
+            &b.field.selection_set,
+            &mut b_subfields,
+            &mut HashSet::new(),
+        );
+
+        for (response_name, a_subfields) in &a_subfields {
+            let Some(b_subfields) = b_subfields.get(response_name) else {
+                continue;
+            };
+            for a_subfield in a_subfields {
+                for b_subfield in b_subfields {
+                    if let Some(reason) =
+                        self.find_conflict(a_subfield, b_subfield, mutually_exclusive)
+                    {
+                        return Some(format!(
+                            "subfields \"{response_name}\" conflict because {reason}"
+                        ));
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    fn do_types_conflict(&self, a: &TypeRef, b: &TypeRef) -> bool {
+        use TypeRefContainer::{List, NonNull};
+
+        match (a, b) {
+            (TypeRef::Container(List { of_type: a }), TypeRef::Container(List { of_type: b }))
+            | (
+                TypeRef::Container(NonNull { of_type: a }),
+                TypeRef::Container(NonNull { of_type: b }),
+            ) => self.do_types_conflict(a, b),
+            (TypeRef::Container(_), _) | (_, TypeRef::Container(_)) => true,
+            (TypeRef::To { name: a }, TypeRef::To { name: b }) => {
+                let is_leaf = |name: &str| {
+                    matches!(
+                        self.types.get(name),
+                        Some(NamedType::Scalar { .. } | NamedType::Enum { .. })
+                    )
+                };
+                (is_leaf(a) || is_leaf(b)) && a != b
+            }
+        }
+    }
+}
+
+fn is_variable_usage_allowed(
+    variable_type: &TypeRef,
+    variable_has_default: bool,
+    location_type: &TypeRef,
+    location_has_default: bool,
+) -> bool {
+    if let TypeRef::Container(TypeRefContainer::NonNull {
+        of_type: nullable_location_type,
+    }) = location_type
+    {
+        if !variable_type.is_non_null() {
+            return (variable_has_default || location_has_default)
+                && are_types_compatible(variable_type, nullable_location_type);
+        }
+    }
+
+    are_types_compatible(variable_type, location_type)
+}
+
+fn are_types_compatible(variable_type: &TypeRef, location_type: &TypeRef) -> bool {
+    use TypeRefContainer::{List, NonNull};
+
+    match (variable_type, location_type) {
+        (
+            TypeRef::Container(NonNull { of_type: variable }),
+            TypeRef::Container(NonNull { of_type: location }),
+        )
+        | (
+            TypeRef::Container(List { of_type: variable }),
+            TypeRef::Container(List { of_type: location }),
+        ) => are_types_compatible(variable, location),
+        (_, TypeRef::Container(NonNull { .. })) => false,
+        (TypeRef::Container(NonNull { of_type: variable }), location) => {
+            are_types_compatible(variable, location)
+        }
+        (TypeRef::Container(List { .. }), _) | (_, TypeRef::Container(List { .. })) => false,
+        (TypeRef::To { name: variable }, TypeRef::To { name: location }) => variable == location,
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use graphql_parser::{parse_query, parse_schema};
+
+    use crate::graphql::schema::Schema;
+
+    const SCHEMA: &str = r#"
+schema {
+  query: Query
+}
+
+type Query {
+  pet(id: ID!): Pet
+  pets(kind: Kind, limit: Int): [Pet!]!
+  allPets: [Pet!]! @deprecated(reason: "Use pets")
+  search(filter: Filter!): [SearchResult!]!
+  cats(lives: Int! = 9): [Cat!]!
+}
+
+input Filter {
+  text: String!
+  kinds: [Kind!]
+  exact: Boolean! = false
+}
+
+enum Kind {
+  DOG
+  CAT
+}
+
+interface Pet {
+  name: String!
+}
+
+type Dog implements Pet {
+  name: String!
+  barks: Boolean!
+}
+
+type Cat implements Pet {
+  name: String!
+  meows: Boolean!
+  lives: Int
+}
+
+type Human {
+  name: String!
+}
+
+union SearchResult = Dog | Cat | Human
+"#;
+
+    fn validate(document: &str) -> Vec<String> {
+        let schema: Schema = parse_schema::<String>(SCHEMA)
+            .expect("parsing schema")
+            .try_into()
+            .expect("converting schema");
+        let document = parse_query::<String>(document).expect("parsing document");
+
+        super::validate(&schema, &document)
+            .errors
+            .into_iter()
+            .map(|error| error.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn valid_document_has_no_errors() {
+        let errors = validate(
+            r#"
+query Pets($kind: Kind, $limit: Int = 10) {
+  __typename
+  pets(kind: $kind, limit: $limit) {
+    ...PetName
+    ... on Dog {
+      barks
+    }
+  }
+  search(filter: { text: "rex", kinds: DOG }) {
+    __typename
+    ... on Human {
+      name
+    }
+  }
+}
+
+fragment PetName on Pet {
+  name
+}
+"#,
+        );
+
+        assert_eq!(Vec::<String>::new(), errors);
+    }
+
+    #[test]
+    fn defaults_make_arguments_optional() {
+        let errors = validate(
+            r#"
+query Defaults($lives: Int) {
+  cats {
+    name
+  }
+  more: cats(lives: $lives) {
+    name
+  }
+  search(filter: { text: "rex" }) {
+    __typename
+  }
+}
+"#,
+        );
+
+        assert_eq!(Vec::<String>::new(), errors);
+    }
+
+    #[test]
+    fn introspection_selections() {
+        let errors = validate(
+            r#"
+query Introspect {
+  __schema {
+    queryType {
+      name
+    }
+  }
+  __type(name: "Pet") {
+    kind
+    nope
+  }
+}
+"#,
+        );
+
+        assert_eq!(
+            vec![r#"Cannot query field "nope" on type "__Type" at 10:5"#],
+            errors
+        );
+    }
+
+    #[test]
+    fn fields_and_arguments() {
+        let errors = validate(
+            r#"
+query {
+  pet(id: "1", species: "dog") {
+    name {
+      length
+    }
+    age
+  }
+  pets(kind: FISH, limit: "ten")
+  search {
+    __typename
+  }
+}
+"#,
+        );
+
+        assert_eq!(
+            vec![
+                r#"Unknown argument "species" on field "Query.pet" at 3:3"#,
+                r#"Field "name" must not have a selection since type "String!" has no subfields at 4:5"#,
+                r#"Cannot query field "age" on type "Pet" at 7:5"#,
+                r#"Argument "kind" on field "Query.pets" has invalid value FISH. Expected type "Kind" at 9:3"#,
+                r#"Argument "limit" on field "Query.pets" has invalid value "ten". Expected type "Int" at 9:3"#,
+                r#"Field "pets" of type "[Pet!]!" must have a selection of subfields at 9:3"#,
+                r#"Argument "filter" of type "Filter!" is required on field "Query.search", but it was not provided at 10:3"#,
+            ],
+            errors
+        );
+    }
+
+    #[test]
+    fn variables() {
+        let errors = validate(
+            r#"
+query Pet($id: ID, $unused: Int, $pet: Pet) {
+  pet(id: $id) {
+    name
+  }
+  pets(limit: $limit) {
+    name
+  }
+}
+"#,
+        );
+
+        assert_eq!(
+            vec![
+                r#"Variable "$unused" is never used in operation "Pet" at 2:20"#,
+                r#"Variable "$pet" cannot be non-input type "Pet" at 2:34"#,
+                r#"Variable "$pet" is never used in operation "Pet" at 2:34"#,
+                r#"Variable "$id" of type "ID" used in position expecting type "ID!" at 3:3"#,
+                r#"Variable "$limit" is not defined by operation "Pet" at 6:3"#,
+            ],
+            errors
+        );
+    }
+
+    #[test]
+    fn fragments() {
+        let errors = validate(
+            r#"
+query {
+  pets {
+    ...Human
+    ... on Kind {
+      name
+    }
+    ...Missing
+    ...A
+  }
+}
+
+fragment Human on Human {
+  name
+}
+
+fragment A on Pet {
+  ...B
+}
+
+fragment B on Pet {
+  ...A
+}
+"#,
+        );
+
+        assert_eq!(
+            vec![
+                r#"Fragment "Human" cannot be spread here as objects of type "Pet" can never be of type "Human" at 4:8"#,
+                r#"Fragment cannot condition on non composite type "Kind" at 5:9"#,
+                r#"Unknown fragment "Missing" at 8:8"#,
+                r#"Cannot spread fragment "A" within itself via "B" at 22:6"#,
+            ],
+            errors
+        );
+    }
+
+    #[test]
+    fn overlapping_fields() {
+        let errors = validate(
+            r#"
+query {
+  pets {
+    name: __typename
+    name
+  }
+  search(filter: { text: "" }) {
+    ... on Dog {
+      flag: barks
+    }
+    ... on Cat {
+      flag: lives
+    }
+  }
+  first: pets(limit: 1) {
+    name
+  }
+  first: pets(limit: 2) {
+    name
+  }
+}
+"#,
+        );
+
+        assert_eq!(
+            vec![
+                r#"Fields "name" conflict because "__typename" and "name" are different fields. Use different aliases on the fields to fetch both if this was intentional at 5:5"#,
+                r#"Fields "flag" conflict because they return conflicting types "Boolean!" and "Int". Use different aliases on the fields to fetch both if this was intentional at 12:7"#,
+                r#"Fields "first" conflict because they have differing arguments. Use different aliases on the fields to fetch both if this was intentional at 18:3"#,
+            ],
+            errors
+        );
+    }
+
+    #[test]
+    fn deprecated_fields_are_warnings() {
+        let schema: Schema = parse_schema::<String>(SCHEMA)
+            .expect("parsing schema")
+            .try_into()
+            .expect("converting schema");
+        let document = parse_query::<String>(
+            r#"
+query {
+  allPets {
+    name
+  }
+}
+"#,
+        )
+        .expect("parsing document");
+
+        let validation = super::validate(&schema, &document);
+
+        assert!(validation.errors.is_empty());
+        assert_eq!(
+            vec![r#"Field "Query.allPets" is deprecated: Use pets at 3:3"#],
+            validation
+                .warnings
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        );
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use eyre::{eyre, Result};
 use graphql_client::GraphQLQuery;
 use serde::{Deserialize, Serialize};
@@ -19,6 +21,16 @@ pub struct Data {
 )]
 struct IntrospectionQuery;
 
+/// For servers predating the October 2021 spec, which reject `specifiedByURL` and `isRepeatable`
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "src/graphql/introspection_schema.graphql",
+    query_path = "src/graphql/introspection_query_legacy.graphql",
+    response_derives = "Serialize",
+    variable_derives = "Deserialize"
+)]
+struct LegacyIntrospectionQuery;
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(untagged)]
 pub enum Response {
@@ -32,10 +44,34 @@ pub enum Response {
 }
 
 impl Response {
-    pub async fn fetch(ctx: &app::Context, url: &str, no_ssl: bool) -> Result<Self> {
+    pub async fn fetch(
+        ctx: &app::Context,
+        url: &str,
+        no_ssl: bool,
+        headers: &HashMap<&str, &str>,
+    ) -> Result<Self> {
         let body = IntrospectionQuery::build_query(introspection_query::Variables {});
 
-        let json = cross::net::fetch_json(url, no_ssl, body).await?;
+        let json = cross::net::fetch_json(url, no_ssl, headers, body).await?;
+
+        print_info!(ctx, 3, "Recieved json: {}", json);
+
+        let response = serde_json::from_value(json)?;
+        let Self::Error { data: None, errors } = &response else {
+            return Ok(response);
+        };
+        if !rejects_october_2021_fields(errors) {
+            return Ok(response);
+        }
+
+        print_info!(
+            ctx,
+            1,
+            "Introspection failed, retrying without October 2021 fields: {errors}"
+        );
+        let body = LegacyIntrospectionQuery::build_query(legacy_introspection_query::Variables {});
+
+        let json = cross::net::fetch_json(url, no_ssl, headers, body).await?;
 
         print_info!(ctx, 3, "Recieved json: {}", json);
 
@@ -61,10 +97,24 @@ impl Response {
     }
 }
 
+/// Whether the errors look like the server rejecting `specifiedByURL` or `isRepeatable`
+fn rejects_october_2021_fields(errors: &serde_json::Value) -> bool {
+    let errors = errors.to_string();
+    [
+        "specifiedByURL",
+        "isRepeatable",
+        "Cannot query field",
+        "Unknown field",
+    ]
+    .iter()
+    .any(|needle| errors.contains(needle))
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 #[cfg(test)]
 mod tests {
-    use super::Response;
+    use super::{rejects_october_2021_fields, Response};
+    use crate::graphql::schema::NamedType;
 
     #[tokio::test]
     async fn response_json() {
@@ -78,4 +128,86 @@ mod tests {
 
         insta::assert_snapshot!(serded_response);
     }
+
+    #[test]
+    fn interface_interfaces_from_any_spec() {
+        let response: Response = serde_json::from_str(
+            r#"{
+  "data": {
+    "__schema": {
+      "queryType": { "name": "Query" },
+      "mutationType": null,
+      "subscriptionType": null,
+      "directives": [],
+      "types": [
+        {
+          "kind": "INTERFACE",
+          "name": "Old",
+          "description": null,
+          "fields": [],
+          "possibleTypes": [],
+          "interfaces": null
+        },
+        {
+          "kind": "INTERFACE",
+          "name": "New",
+          "description": null,
+          "fields": [],
+          "possibleTypes": [],
+          "interfaces": [{ "kind": "INTERFACE", "name": "Old", "ofType": null }]
+        }
+      ]
+    }
+  }
+}"#,
+        )
+        .expect("response deserialization");
+
+        let schema = response.schema().expect("response must have schema");
+
+        let interfaces = schema
+            .types
+            .iter()
+            .map(|t| match t {
+                NamedType::Interface {
+                    name, interfaces, ..
+                } => (name.as_str(), interfaces.len()),
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec![("Old", 0), ("New", 1)], interfaces);
+    }
+
+    #[test]
+    fn legacy_query_matches_query() {
+        let expected = include_str!("graphql/introspection_query.graphql")
+            .replacen(
+                "query IntrospectionQuery",
+                "query LegacyIntrospectionQuery",
+                1,
+            )
+            .replace("      isRepeatable\n", "")
+            .replace("  specifiedByURL\n", "");
+        let legacy = include_str!("graphql/introspection_query_legacy.graphql")
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .map(|line| format!("{line}\n"))
+            .collect::<String>();
+
+        assert_eq!(expected, legacy);
+    }
+
+    #[test]
+    fn retries_only_for_unknown_fields() {
+        let errors = |message: &str| serde_json::json!([{ "message": message }]);
+
+        assert!(rejects_october_2021_fields(&errors(
+            "Cannot query field \"specifiedByURL\" on type \"__Type\"."
+        )));
+        assert!(rejects_october_2021_fields(&errors(
+            "Cannot query field \"isRepeatable\" on type \"__Directive\"."
+        )));
+        assert!(!rejects_october_2021_fields(&errors("Not authorized")));
+    }
 }
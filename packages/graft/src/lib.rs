@@ -3,15 +3,60 @@ mod cross;
 mod gen;
 mod graphql;
 mod introspection;
+mod output;
 mod typescript;
 mod util;
+mod watch;
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
 
 use clap::Parser;
-use eyre::Result;
+use eyre::{eyre, Result};
+use futures_util::future::join_all;
 use graphql_parser::schema::{parse_schema, Document};
 
 use crate::app::cli;
-use crate::gen::generate_typescript;
+use crate::app::config::{GenPlans, SchemaGenPlan, SchemaSource};
+use crate::gen::{generate_near_operation_files, generate_typescript};
+use crate::graphql::schema::{DirectiveLocation, Schema};
+use crate::output::Output;
+
+async fn load_schema(ctx: &app::Context, schema_gen_plan: &SchemaGenPlan) -> Result<Schema> {
+    match schema_gen_plan.source()? {
+        SchemaSource::Url(url) => introspection::Response::fetch(
+            ctx,
+            url.as_str(),
+            schema_gen_plan.no_ssl,
+            &schema_gen_plan.headers(),
+        )
+        .await?
+        .schema(),
+        SchemaSource::Path(path) => read_schema_file(path),
+    }
+}
+
+/// Reads a schema from either an SDL file or an introspection response JSON file
+fn read_schema_file(path: &Path) -> Result<Schema> {
+    let schema_string = cross::fs::read_to_string(path)?;
+
+    if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::from_str::<introspection::Response>(&schema_string)?.schema()
+    } else {
+        parse_schema::<String>(&schema_string)?.try_into()
+    }
+}
+
+fn diff_schema_files(old: &Path, new: &Path, json: bool) -> Result<String> {
+    let changes = graphql::diff::diff(&read_schema_file(old)?, &read_schema_file(new)?);
+
+    if json {
+        Ok(format!("{}\n", serde_json::to_string_pretty(&changes)?))
+    } else {
+        Ok(graphql::diff::report(&changes))
+    }
+}
 
 #[allow(clippy::missing_errors_doc)]
 pub async fn run() -> Result<()> {
@@ -30,6 +75,7 @@ pub async fn run() -> Result<()> {
     let ctx = app::Context {
         verbose: cli.verbose,
         config_location: cli.config_location.clone(),
+        log: None,
     };
 
     if let Some(working_dir) = &cli.working_directory {
@@ -38,8 +84,28 @@ pub async fn run() -> Result<()> {
         debug_log!("Current directory set to {:?}", cross::env::current_dir()?);
     }
 
+    if let Some(cli::Mode::Diff { old, new, json }) = &cli.mode {
+        cross_print!("{}", diff_schema_files(old, new, *json)?);
+        return Ok(());
+    }
+
+    debug_log!("Finding config file");
+    let config_path = app::Config::find(cli.config_location.as_deref()).unwrap_or_else(|err| {
+        cross_eprintln!("Failed to load config: {}", err);
+        cross::process::exit(1);
+    });
+
+    print_info!(ctx, 1, "Using config file {}", config_path.display());
+
+    // Paths in a config found in a parent directory are relative to that directory
+    if cli.config_location.is_none() {
+        if let Some(config_dir) = config_path.parent() {
+            cross::env::set_current_dir(config_dir)?;
+        }
+    }
+
     debug_log!("Loading config file");
-    let config = app::Config::load(cli.config_location.as_deref()).unwrap_or_else(|err| {
+    let mut config = app::Config::load(&config_path).unwrap_or_else(|err| {
         cross_eprintln!("Failed to load config: {}", err);
         cross::process::exit(1);
     });
@@ -48,46 +114,205 @@ pub async fn run() -> Result<()> {
 
     print_info!(ctx, 1, "Context generated!");
 
+    config.select(&cli.selection)?;
+
     let mode = cli.mode.unwrap_or(cli::Mode::All);
 
-    for (name, plans) in config.generates {
-        if let (cli::Mode::All, Some(schema_gen_plan)) = (&mode, plans.schema_gen_plan) {
-            print_info!(ctx, 1, "Fetching schema for {name}...");
-            let schema = introspection::Response::fetch(
-                &ctx,
-                schema_gen_plan.url.0.as_str(),
-                schema_gen_plan.no_ssl,
-            )
-            .await?
-            .schema()?;
-            print_info!(ctx, 1, "Schema fetched!");
-
-            if let Some(json_path) = schema_gen_plan.out.json_path {
+    match mode {
+        cli::Mode::Watch => {
+            return watch::watch(&ctx, &config_path, &cli.selection, config).await;
+        }
+        cli::Mode::List => {
+            cross_print!("{}", list_entries(&ctx, &config)?);
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    let mut output = match mode {
+        cli::Mode::Check => Output::Check(BTreeMap::new()),
+        _ => Output::Write,
+    };
+
+    for wave in config.dependency_waves()? {
+        run_wave(&ctx, &mode, &config, &wave, &mut output).await?;
+    }
+
+    let stale_diffs = output.stale_diffs()?;
+    if !stale_diffs.is_empty() {
+        for diff in &stale_diffs {
+            cross_print!("{diff}");
+        }
+        return Err(eyre!(
+            "{} generated file(s) are out of date. Run graft to regenerate them.",
+            stale_diffs.len()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Runs entries concurrently, then logs them and reports the first failure in name order
+async fn run_wave(
+    ctx: &app::Context,
+    mode: &cli::Mode,
+    config: &app::Config,
+    wave: &[&str],
+    output: &mut Output,
+) -> Result<()> {
+    let results = join_all(wave.iter().map(|name| async {
+        let entry_ctx = ctx.buffered();
+        let mut entry_output = output.fork();
+        let result = run_gen_plans(
+            &entry_ctx,
+            mode,
+            name,
+            &config.generates[*name],
+            &mut entry_output,
+        )
+        .await;
+        (entry_ctx.take_log(), entry_output, result)
+    }))
+    .await;
+
+    let mut first_err = None;
+
+    for (log, entry_output, result) in results {
+        log.flush();
+        output.merge(entry_output);
+        if let Err(err) = result {
+            first_err.get_or_insert(err);
+        }
+    }
+
+    first_err.map_or(Ok(()), Err)
+}
+
+/// Each `generates` entry, in name order, with the files it reads and writes
+fn list_entries(ctx: &app::Context, config: &app::Config) -> Result<String> {
+    let mut names = config.generates.keys().collect::<Vec<_>>();
+    names.sort();
+
+    let mut list = String::new();
+
+    for name in names {
+        let plans = &config.generates[name];
+
+        writeln!(list, "{}", console::style(name).bold())?;
+
+        writeln!(list, "  inputs:")?;
+        match plans.planned_inputs(ctx.config_location.as_deref()) {
+            Ok(inputs) => {
+                for input in inputs {
+                    writeln!(list, "    {input}")?;
+                }
+            }
+            Err(err) => writeln!(list, "    {} {err}", console::style("Error").red())?,
+        }
+
+        writeln!(list, "  outputs:")?;
+        match plans.planned_outputs(ctx.config_location.as_deref()) {
+            Ok(outputs) => {
+                for output in outputs {
+                    writeln!(list, "    {}", output.display())?;
+                }
+            }
+            Err(err) => writeln!(list, "    {} {err}", console::style("Error").red())?,
+        }
+    }
+
+    Ok(list)
+}
+
+async fn run_gen_plans(
+    ctx: &app::Context,
+    mode: &cli::Mode,
+    name: &str,
+    plans: &GenPlans,
+    output: &mut Output,
+) -> Result<()> {
+    if let Some(schema_gen_plan) = &plans.schema_gen_plan {
+        let run_schema_gen_plan = match mode {
+            cli::Mode::All | cli::Mode::Check => true,
+            // Only local schemas are cheap enough to reload on every change
+            cli::Mode::Watch => matches!(schema_gen_plan.source()?, SchemaSource::Path(_)),
+            cli::Mode::Typescript | cli::Mode::List | cli::Mode::Diff { .. } => false,
+        };
+
+        if run_schema_gen_plan {
+            print_info!(ctx, 1, "Loading schema for {name}...");
+            let schema = load_schema(ctx, schema_gen_plan).await?;
+            print_info!(ctx, 1, "Schema loaded!");
+
+            if let (cli::Mode::All, true, Some(ast_path)) =
+                (mode, schema_gen_plan.diff, &schema_gen_plan.out.ast_path)
+            {
+                if cross::fs::modified(ast_path)?.is_some() {
+                    let previous = read_schema_file(ast_path)?;
+                    let changes = graphql::diff::diff(&previous, &schema);
+                    ctx.print(&format!(
+                        "Schema changes for {name}:\n{}",
+                        graphql::diff::report(&changes)
+                    ));
+                }
+            }
+
+            if let Some(json_path) = &schema_gen_plan.out.json_path {
                 print_info!(ctx, 1, "Emitting schema json");
                 let schema_json = serde_json::to_string_pretty(&schema)?;
-                cross::fs::write_to_file(json_path, &schema_json)?;
+                output.write_to_file(json_path, &schema_json)?;
             }
-            if let Some(ast_path) = schema_gen_plan.out.ast_path {
+            if let Some(ast_path) = &schema_gen_plan.out.ast_path {
                 print_info!(ctx, 1, "Emitting schema ast");
+                for directive in schema.directives_losing_sdl_locations() {
+                    ctx.print_warning(&format!(
+                        "{} Directive @{} can't be written to SDL on VARIABLE_DEFINITION, so {}",
+                        console::style("Warning").yellow(),
+                        directive.name,
+                        if directive
+                            .locations
+                            .iter()
+                            .all(|l| matches!(l, DirectiveLocation::VariableDefinition))
+                        {
+                            "it's left out of the schema ast"
+                        } else {
+                            "that location is left out of the schema ast"
+                        }
+                    ));
+                }
                 let schema_graphql = format!("{}", Document::from(&schema));
-                cross::fs::write_to_file(ast_path, &schema_graphql)?;
+                output.write_to_file(ast_path, &schema_graphql)?;
             }
         }
-        if let Some(typescript_gen_plan) = plans.typescript_gen_plan {
-            print_info!(ctx, 1, "Reading schema ast...");
-            let schema_ast = cross::fs::read_to_string(&typescript_gen_plan.ast)?;
-            let schema_ast = parse_schema::<String>(&schema_ast)?;
-            let schema = schema_ast.try_into()?;
+    }
+    if let Some(typescript_gen_plan) = &plans.typescript_gen_plan {
+        print_info!(ctx, 1, "Reading schema ast...");
+        let schema_ast = output.read_to_string(&typescript_gen_plan.ast)?;
+        let schema_ast = parse_schema::<String>(&schema_ast)?;
+        let schema = schema_ast.try_into()?;
 
-            print_info!(ctx, 1, "Generating typescript...");
+        print_info!(ctx, 1, "Generating typescript...");
+        if typescript_gen_plan.near_operation_file {
+            let files = generate_near_operation_files(
+                ctx,
+                &typescript_gen_plan.options,
+                typescript_gen_plan.document_paths.as_ref(),
+                &schema,
+                &typescript_gen_plan.out,
+            )?;
+
+            for (path, ts) in files {
+                output.write_to_file(&path, &ts)?;
+            }
+        } else {
             let ts = generate_typescript(
-                &ctx,
-                typescript_gen_plan.options,
-                typescript_gen_plan.document_paths,
+                ctx,
+                &typescript_gen_plan.options,
+                typescript_gen_plan.document_paths.as_ref(),
                 &schema,
             )?;
 
-            cross::fs::write_to_file(typescript_gen_plan.out, &ts)?;
+            output.write_to_file(&typescript_gen_plan.out, &ts)?;
         }
     }
 
@@ -102,3 +327,106 @@ pub async fn node_main() -> eyre::Result<(), wasm_bindgen::JsValue> {
         .await
         .map_err(|err| wasm_bindgen::JsValue::from_str(&err.to_string()))
 }
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use graphql_parser::schema::Document;
+
+    use crate::app::{
+        self,
+        config::{SchemaGenOut, SchemaGenPlan, TypescriptOptions},
+    };
+
+    fn schema_gen_plan(path: &str) -> SchemaGenPlan {
+        SchemaGenPlan {
+            url: None,
+            path: Some(path.into()),
+            no_ssl: false,
+            headers: HashMap::new(),
+            diff: false,
+            out: SchemaGenOut {
+                ast_path: None,
+                json_path: None,
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn load_schema_from_json_and_sdl_paths() {
+        let ctx = app::Context {
+            verbose: 0,
+            config_location: None,
+            log: None,
+        };
+
+        let from_json = super::load_schema(
+            &ctx,
+            &schema_gen_plan("fixtures/star-wars-introspection-response.json"),
+        )
+        .await
+        .expect("loading schema from introspection json");
+        let sdl = Document::from(&from_json).to_string();
+
+        // Unique per process, so concurrent runs of the suite don't overwrite each other's file
+        let sdl_path = std::env::temp_dir().join(format!(
+            "graft-load-schema-from-json-and-sdl-paths-{}.graphql",
+            std::process::id()
+        ));
+        std::fs::write(&sdl_path, &sdl).expect("writing sdl");
+
+        let from_sdl = super::load_schema(
+            &ctx,
+            &schema_gen_plan(sdl_path.to_str().expect("utf-8 temp path")),
+        )
+        .await
+        .expect("loading schema from sdl");
+
+        std::fs::remove_file(&sdl_path).expect("removing sdl");
+
+        assert_eq!(sdl, Document::from(&from_sdl).to_string());
+    }
+
+    #[tokio::test]
+    async fn load_schema_from_sdl_without_schema_definition_or_scalars() {
+        let ctx = app::Context::default();
+
+        let sdl_path = std::env::temp_dir().join(format!(
+            "graft-load-schema-from-sdl-without-schema-definition-{}.graphql",
+            std::process::id()
+        ));
+        std::fs::write(
+            &sdl_path,
+            "type Query { hero: Hero }\n\ntype Hero { name: String! age: Int }\n",
+        )
+        .expect("writing sdl");
+
+        let schema = super::load_schema(
+            &ctx,
+            &schema_gen_plan(sdl_path.to_str().expect("utf-8 temp path")),
+        )
+        .await;
+
+        std::fs::remove_file(&sdl_path).expect("removing sdl");
+
+        let schema = schema.expect("loading schema from sdl");
+        assert_eq!("Query", schema.query_type.name);
+        assert!(schema.mutation_type.is_none());
+
+        let typescript = crate::gen::generate_typescript_with_document(
+            &ctx,
+            &TypescriptOptions::default(),
+            &schema,
+            Some(
+                graphql_parser::parse_query::<String>("query Hero { hero { name age } }")
+                    .expect("parsing document"),
+            ),
+        )
+        .expect("generating typescript");
+        assert!(typescript.contains("export type StringScalar = string;"));
+        assert!(typescript.contains("export type IntScalar = number;"));
+        assert!(!typescript.contains("BooleanScalar"));
+    }
+}
@@ -0,0 +1,128 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use eyre::Result;
+use similar::TextDiff;
+
+use crate::cross;
+
+/// Where generated files end up: written straight to disk, or held in memory so they can be
+/// compared against what's already on disk.
+pub enum Output {
+    Write,
+    Check(BTreeMap<PathBuf, String>),
+}
+
+impl Output {
+    pub fn write_to_file<P: AsRef<Path>>(&mut self, path: P, data: &str) -> Result<()> {
+        match self {
+            Output::Write => cross::fs::write_to_file(path, data),
+            Output::Check(planned) => {
+                planned.insert(path.as_ref().to_owned(), data.to_owned());
+                Ok(())
+            }
+        }
+    }
+
+    /// A separate output for work running alongside this one, to be merged back in afterwards
+    pub fn fork(&self) -> Self {
+        match self {
+            Output::Write => Output::Write,
+            Output::Check(planned) => Output::Check(planned.clone()),
+        }
+    }
+
+    pub fn merge(&mut self, other: Output) {
+        if let (Output::Check(planned), Output::Check(other_planned)) = (self, other) {
+            planned.extend(other_planned);
+        }
+    }
+
+    /// Reads a file as it would be if every output planned so far had been written
+    pub fn read_to_string<P: AsRef<Path>>(&self, path: P) -> Result<String> {
+        match self {
+            Output::Check(planned) if planned.contains_key(path.as_ref()) => {
+                Ok(planned[path.as_ref()].clone())
+            }
+            _ => cross::fs::read_to_string(path),
+        }
+    }
+
+    /// Unified diffs for every planned output that differs from what is on disk
+    pub fn stale_diffs(&self) -> Result<Vec<String>> {
+        let Output::Check(planned) = self else {
+            return Ok(vec![]);
+        };
+
+        let mut diffs = vec![];
+
+        for (path, planned_data) in planned {
+            let current_data = match cross::fs::modified(path)? {
+                Some(_) => cross::fs::read_to_string(path)?,
+                None => String::new(),
+            };
+
+            if current_data == *planned_data {
+                continue;
+            }
+
+            let path = path.display();
+            let mut diff = String::new();
+            write!(
+                diff,
+                "{}",
+                TextDiff::from_lines(&current_data, planned_data)
+                    .unified_diff()
+                    .header(&format!("a/{path}"), &format!("b/{path}"))
+            )?;
+            diffs.push(diff);
+        }
+
+        Ok(diffs)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::Output;
+
+    #[test]
+    fn check_reads_planned_outputs_and_diffs_them() {
+        let path = std::env::temp_dir().join(format!(
+            "graft-output-check-test-{}.txt",
+            std::process::id()
+        ));
+        std::fs::write(&path, "a\nb\nc\n").expect("writing");
+
+        let mut output = Output::Check(BTreeMap::new());
+        output
+            .write_to_file(&path, "a\nB\nc\n")
+            .expect("planning output");
+
+        assert_eq!(
+            "a\nB\nc\n",
+            output
+                .read_to_string(&path)
+                .expect("reading planned output")
+        );
+        assert_eq!(
+            "a\nb\nc\n",
+            std::fs::read_to_string(&path).expect("reading file")
+        );
+
+        let diffs = output.stale_diffs().expect("diffing");
+        std::fs::remove_file(&path).expect("removing file");
+
+        let path = path.display();
+        assert_eq!(
+            vec![format!(
+                "--- a/{path}\n+++ b/{path}\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n"
+            )],
+            diffs
+        );
+    }
+}
@@ -3,21 +3,26 @@ use std::fmt::Write;
 use convert_case::{Case, Casing};
 use eyre::{eyre, Result};
 use graphql_parser::query::{
-    Definition, FragmentSpread, InlineFragment, OperationDefinition, TypeCondition,
+    Definition, FragmentDefinition, FragmentSpread, InlineFragment, OperationDefinition,
+    TypeCondition,
 };
 
 use crate::{
     gen::Buffer,
     graphql::{
         query::{self as ac, Operation},
-        schema::{Field, NamedType, Type, TypeRef, TypeRefContainer},
+        schema::{NamedType, Type, TypeRef, TypeRefContainer},
     },
-    typescript::{self, Typescriptable, TypescriptableWithBuffer, WithContext},
+    typescript::{
+        self, default_tag, deprecation_tag, Export, Typescriptable, TypescriptableWithBuffer,
+        WithContext,
+    },
+    util::Named,
 };
 
-use graphql_parser::query::{Field as SelectedField, Selection, SelectionSet};
+use graphql_parser::query::{Directive, Field as SelectedField, Selection, SelectionSet, Value};
 
-impl<'a, 'b, 'c> TypescriptableWithBuffer for WithContext<'a, 'b, 'c, Definition<'_, String>> {
+impl<'a, 'b, 'c> TypescriptableWithBuffer for WithContext<'a, 'b, 'c, Definition<'static, String>> {
     fn as_typescript_on(&self, buffer: &mut Buffer) -> Result<()> {
         let definition = self.target;
         let ctx = self.ctx;
@@ -32,7 +37,7 @@ impl<'a, 'b, 'c> TypescriptableWithBuffer for WithContext<'a, 'b, 'c, Definition
                       ));
                     }
                     OperationDefinition::Query(query) => (
-                        Operation::from(OperationDefinition::Query(query.clone())),
+                        OperationDefinition::Query(query.clone()),
                         query
                             .name
                             .as_ref()
@@ -44,7 +49,7 @@ impl<'a, 'b, 'c> TypescriptableWithBuffer for WithContext<'a, 'b, 'c, Definition
                         &ctx.index.query,
                     ),
                     OperationDefinition::Mutation(mutation) => (
-                        Operation::from(OperationDefinition::Mutation(mutation.clone())),
+                        OperationDefinition::Mutation(mutation.clone()),
                         mutation
                             .name
                             .as_ref()
@@ -59,7 +64,7 @@ impl<'a, 'b, 'c> TypescriptableWithBuffer for WithContext<'a, 'b, 'c, Definition
                             .ok_or_else(|| eyre!("Mutation type does not exist in TypeIndex"))?,
                     ),
                     OperationDefinition::Subscription(subscription) => (
-                        Operation::from(OperationDefinition::Subscription(subscription.clone())),
+                        OperationDefinition::Subscription(subscription.clone()),
                         subscription
                             .name
                             .as_ref()
@@ -84,7 +89,14 @@ impl<'a, 'b, 'c> TypescriptableWithBuffer for WithContext<'a, 'b, 'c, Definition
                 ) = operation_bundle;
                 let operation_name = operation_name.to_case(Case::Pascal);
 
-                let document = ac::Document::new(vec![ac::Definition::Operation(operation_ast)]);
+                let operation_ast = Operation::from(with_typenames_on_operation(
+                    operation_ast,
+                    operation_type,
+                    ctx,
+                )?);
+                let mut definitions = vec![ac::Definition::Operation(operation_ast)];
+                definitions.extend(spread_fragment_definitions(selection_set, ctx)?);
+                let document = ac::Document::new(definitions);
 
                 let document_json = serde_json::to_string(&document)?;
 
@@ -110,7 +122,9 @@ impl<'a, 'b, 'c> TypescriptableWithBuffer for WithContext<'a, 'b, 'c, Definition
                     "export const {document_name} = {document_json} as unknown as {document_type_name}<{selection_set_name}, {args_name}>;",
                     document_type_name = ctx.options.document_import.type_name()
                 )?;
+                ctx.define(&document_name, Export::Value);
 
+                ctx.define(&args_name, Export::Type);
                 if variable_definitions.is_empty() {
                     writeln!(
                         buffer.args,
@@ -120,55 +134,113 @@ impl<'a, 'b, 'c> TypescriptableWithBuffer for WithContext<'a, 'b, 'c, Definition
                     writeln!(buffer.args, "export type {args_name} = {{")?;
                     for def in variable_definitions {
                         let ts_type = TypeRef::from(def.var_type.clone());
-                        if ts_type.is_non_null() {
+                        if let Some(tag) =
+                            default_tag(def.default_value.as_ref().map(ToString::to_string))
+                        {
+                            writeln!(buffer.args, "  /** {tag} */")?;
+                        }
+                        // A variable with a default can be left out, but still can't be null
+                        if ts_type.is_non_null() && def.default_value.is_none() {
                             writeln!(
                                 buffer.args,
                                 "  {}: {},",
                                 def.name,
-                                ctx.with(&ts_type).as_typescript()?
+                                ctx.with(&ts_type).as_input_typescript()?
                             )?;
                         } else {
                             writeln!(
                                 buffer.args,
                                 "  {}?: {},",
                                 def.name,
-                                ctx.with(&ts_type).as_typescript()?
+                                ctx.with(&ts_type).as_input_typescript()?
                             )?;
                         }
                     }
                     writeln!(buffer.args, "}}")?;
                 }
 
-                let NamedType::Object { fields: operation_fields, .. } = operation_type else {
+                // Variables can be left out if none of them are required
+                let variables_optional = variable_definitions.iter().all(|def| {
+                    !TypeRef::from(def.var_type.clone()).is_non_null()
+                        || def.default_value.is_some()
+                });
+
+                if ctx.options.sdk {
+                    typescript::sdk::write_method(
+                        &format!("{operation_name}{operation_type_name}").to_case(Case::Camel),
+                        &document_name,
+                        &selection_set_name,
+                        &args_name,
+                        variables_optional,
+                        operation_type_name == "Subscription",
+                        buffer,
+                    )?;
+                }
+
+                if let Some(library) = ctx.options.react_hooks {
+                    typescript::react_hooks::write_hooks(
+                        ctx,
+                        library,
+                        &typescript::react_hooks::HookedOperation {
+                            name: &operation_name,
+                            operation_type_name,
+                            document_name: &document_name,
+                            selection_set_name: &selection_set_name,
+                            args_name: &args_name,
+                            variables_optional,
+                        },
+                        &mut buffer.react_hooks,
+                    )?;
+                }
+
+                if ctx.options.zod {
+                    typescript::zod::write_args_schema(
+                        ctx,
+                        &args_name,
+                        variable_definitions,
+                        &mut buffer.zod_args,
+                    )?;
+                }
+
+                if !matches!(operation_type, NamedType::Object { .. }) {
                     return Err(eyre!("Top-level operation must be an object"));
-                };
+                }
 
                 write!(buffer.selection_sets, "export type {selection_set_name} = ",)?;
+                ctx.define(&selection_set_name, Export::Type);
                 recursively_typescriptify_selected_object_fields(
-                    selection_set,
+                    &[selection_set],
                     &mut buffer.selection_sets,
-                    operation_fields,
+                    operation_type,
                     ctx,
+                    false,
                 )?;
                 writeln!(buffer.selection_sets, ";")?;
             }
             Definition::Fragment(fragment) => {
-                let definition = ac::Definition::from(Definition::Fragment(fragment.clone()));
-                let document = ac::Document::new(vec![definition]);
+                let mut definitions = vec![ac::Definition::from(Definition::Fragment(
+                    with_typenames_on_fragment(fragment, ctx)?,
+                ))];
+                definitions.extend(spread_fragment_definitions(&fragment.selection_set, ctx)?);
+                let document = ac::Document::new(definitions);
 
                 let document_json = serde_json::to_string(&document)?;
 
-                writeln!(buffer.fragments, "export const {name}FragmentDocument = {document_json} as unknown as TypedQueryDocumentNode<{name}Fragment{selection_set_suffix}, unknown>", name = fragment.name.to_case(Case::Pascal), selection_set_suffix = ctx.options.selection_set_suffix)?;
+                let name = fragment.name.to_case(Case::Pascal);
+                let document_name = format!("{name}FragmentDocument");
+                let selection_set_name = format!(
+                    "{name}Fragment{selection_set_suffix}",
+                    selection_set_suffix = ctx.options.selection_set_suffix
+                );
+
+                writeln!(buffer.fragments, "export const {document_name} = {document_json} as unknown as {document_type_name}<{selection_set_name}, unknown>", document_type_name = ctx.options.document_import.type_name())?;
+                ctx.define(&document_name, Export::Value);
 
                 let TypeCondition::On(type_name) = &fragment.type_condition;
-                write!(
-                    buffer.selection_sets,
-                    "export type {}Fragment{selection_set_suffix} = ",
-                    fragment.name.to_case(Case::Pascal),
-                    selection_set_suffix = ctx.options.selection_set_suffix
-                )?;
+                write!(buffer.selection_sets, "export type {selection_set_name} = ")?;
+                ctx.define(&selection_set_name, Export::Type);
                 recursively_typescriptify_selected_field(
-                    &fragment.selection_set,
+                    &[&fragment.selection_set],
                     &mut buffer.selection_sets,
                     &TypeRef::from(ctx.index.get(type_name).ok_or_else(|| {
                         eyre!(
@@ -177,7 +249,7 @@ impl<'a, 'b, 'c> TypescriptableWithBuffer for WithContext<'a, 'b, 'c, Definition
                         )
                     })?),
                     ctx,
-                    &mut false,
+                    false,
                 )?;
                 writeln!(buffer.selection_sets, ";")?;
             }
@@ -187,110 +259,646 @@ impl<'a, 'b, 'c> TypescriptableWithBuffer for WithContext<'a, 'b, 'c, Definition
     }
 }
 
-fn recursively_typescriptify_selected_object_fields(
-    selection_set: &SelectionSet<'_, String>,
-    buffer: &mut String,
-    selectable_fields: &[Field],
-    ctx: &typescript::Context,
+fn collect_fragment_names<'s>(
+    selection_set: &'s SelectionSet<'static, String>,
+    ctx: &'s typescript::Context,
+    fragment_names: &mut Vec<&'s str>,
 ) -> Result<()> {
-    let mut fragment_strings = Vec::<String>::new();
-    write!(buffer, "{{ ")?;
     for selection in &selection_set.items {
         match selection {
-            Selection::Field(SelectedField {
+            Selection::Field(field) => {
+                collect_fragment_names(&field.selection_set, ctx, fragment_names)?;
+            }
+            Selection::FragmentSpread(FragmentSpread {
                 position,
-                alias,
-                name,
-                arguments: _,
-                // TODO: Think about including directives in the Typescript
+                fragment_name,
                 directives: _,
+            }) => {
+                if fragment_names.contains(&fragment_name.as_str()) {
+                    continue;
+                }
+                fragment_names.push(fragment_name);
+
+                let fragment = ctx.fragments.get(fragment_name).ok_or_else(|| {
+                    eyre!("Fragment \"{fragment_name}\" spread at {position} not found")
+                })?;
+                collect_fragment_names(&fragment.selection_set, ctx, fragment_names)?;
+            }
+            Selection::InlineFragment(InlineFragment { selection_set, .. }) => {
+                collect_fragment_names(selection_set, ctx, fragment_names)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Every fragment a selection set spreads, directly or through other fragments, once each in the order
+/// they're first spread. A document must define these for a server to accept it.
+fn spread_fragment_definitions(
+    selection_set: &SelectionSet<'static, String>,
+    ctx: &typescript::Context,
+) -> Result<Vec<ac::Definition>> {
+    let mut fragment_names = vec![];
+    collect_fragment_names(selection_set, ctx, &mut fragment_names)?;
+
+    fragment_names
+        .into_iter()
+        .filter_map(|name| ctx.fragments.get(name))
+        .map(|fragment| {
+            Ok(ac::Definition::from(Definition::Fragment(
+                with_typenames_on_fragment(fragment, ctx)?,
+            )))
+        })
+        .collect()
+}
+
+fn with_typenames_on_operation(
+    mut operation: OperationDefinition<'static, String>,
+    operation_type: &NamedType,
+    ctx: &typescript::Context,
+) -> Result<OperationDefinition<'static, String>> {
+    let selection_set = match &mut operation {
+        OperationDefinition::SelectionSet(selection_set) => selection_set,
+        OperationDefinition::Query(query) => &mut query.selection_set,
+        OperationDefinition::Mutation(mutation) => &mut mutation.selection_set,
+        OperationDefinition::Subscription(subscription) => &mut subscription.selection_set,
+    };
+    add_typenames(selection_set, operation_type, ctx)?;
+
+    Ok(operation)
+}
+
+fn with_typenames_on_fragment(
+    fragment: &FragmentDefinition<'static, String>,
+    ctx: &typescript::Context,
+) -> Result<FragmentDefinition<'static, String>> {
+    let mut fragment = fragment.clone();
+    let TypeCondition::On(type_name) = &fragment.type_condition;
+    let fragment_type = ctx.index.get(type_name).ok_or_else(|| {
+        eyre!(
+            "Type targetted by fragment at {} not found",
+            fragment.position
+        )
+    })?;
+    if matches!(
+        fragment_type,
+        NamedType::Interface { .. } | NamedType::Union { .. }
+    ) {
+        add_typename(&mut fragment.selection_set);
+    }
+    add_typenames(&mut fragment.selection_set, fragment_type, ctx)?;
+
+    Ok(fragment)
+}
+
+/// Selects `__typename` unless the selection set already does
+fn add_typename(selection_set: &mut SelectionSet<'static, String>) {
+    let selects_typename = selection_set.items.iter().any(|selection| {
+        matches!(selection, Selection::Field(field) if field.name == "__typename" && field.alias.is_none())
+    });
+    if !selects_typename {
+        selection_set.items.insert(
+            0,
+            Selection::Field(SelectedField {
+                position: selection_set.span.0,
+                alias: None,
+                name: "__typename".to_owned(),
+                arguments: vec![],
+                directives: vec![],
+                selection_set: SelectionSet {
+                    span: selection_set.span,
+                    items: vec![],
+                },
+            }),
+        );
+    }
+}
+
+/// Selects `__typename` on every interface or union in the selection set, since their types are
+/// discriminated by it and the response must carry it
+fn add_typenames(
+    selection_set: &mut SelectionSet<'static, String>,
+    parent: &NamedType,
+    ctx: &typescript::Context,
+) -> Result<()> {
+    for selection in &mut selection_set.items {
+        match selection {
+            Selection::Field(field) => {
+                let of_type = match (parent, field.name.as_str()) {
+                    (_, "__schema") => ctx.index.get("__Schema"),
+                    (_, "__type") => ctx.index.get("__Type"),
+                    (
+                        NamedType::Object { fields, .. } | NamedType::Interface { fields, .. },
+                        name,
+                    ) => fields
+                        .iter()
+                        .find(|f| f.name == name)
+                        .and_then(|f| ctx.index.get(f.of_type.base_name())),
+                    _ => None,
+                };
+                let Some(of_type) = of_type else {
+                    continue;
+                };
+                if matches!(
+                    of_type,
+                    NamedType::Interface { .. } | NamedType::Union { .. }
+                ) {
+                    add_typename(&mut field.selection_set);
+                }
+                add_typenames(&mut field.selection_set, of_type, ctx)?;
+            }
+            Selection::InlineFragment(InlineFragment {
+                type_condition,
                 selection_set,
+                ..
             }) => {
-                let selected_field = selectable_fields
-                    .iter()
-                    .find(|f| f.name == **name)
-                    .ok_or_else(|| {
-                        eyre!("Tried to select non-existent field '{name}' at {position}")
-                    })?;
-                let field_name = alias.as_ref().unwrap_or(&selected_field.name);
+                let fragment_type = match type_condition {
+                    Some(TypeCondition::On(type_name)) => ctx
+                        .index
+                        .get(type_name)
+                        .ok_or_else(|| eyre!("Type \"{type_name}\" not found"))?,
+                    None => parent,
+                };
+                add_typenames(selection_set, fragment_type, ctx)?;
+            }
+            // Spread fragments are added to the document separately
+            Selection::FragmentSpread(_) => {}
+        }
+    }
 
-                write!(buffer, "{field_name}: ")?;
+    Ok(())
+}
 
-                recursively_typescriptify_selected_field(
-                    selection_set,
-                    buffer,
-                    &selected_field.of_type,
-                    ctx,
-                    &mut true,
-                )?;
+/// Whether a selection carrying these directives will be in the response
+#[derive(Clone, Copy, PartialEq)]
+enum Presence {
+    Always,
+    /// Depends on a variable given to `@include` or `@skip`
+    Maybe,
+    Never,
+}
+
+impl Presence {
+    fn of(directives: &[Directive<'static, String>]) -> Self {
+        let mut presence = Presence::Always;
+
+        for directive in directives {
+            let included_when = match directive.name.as_str() {
+                "include" => true,
+                "skip" => false,
+                _ => continue,
+            };
+
+            let condition = directive
+                .arguments
+                .iter()
+                .find(|(name, _)| name == "if")
+                .map(|(_, value)| value);
+
+            match condition {
+                Some(Value::Boolean(condition)) if *condition != included_when => {
+                    return Presence::Never;
+                }
+                Some(Value::Boolean(_)) => {}
+                _ => presence = Presence::Maybe,
+            }
+        }
+
+        presence
+    }
+}
+
+/// A field in a selection set, along with every selection set requested on it under the same response
+/// key, e.g. by a separate inline fragment
+struct CollectedField<'s> {
+    field: &'s SelectedField<'static, String>,
+    selection_sets: Vec<&'s SelectionSet<'static, String>>,
+    /// The conditional group the field is in, if any
+    group: Option<usize>,
+    /// Whether the field may be missing even when its group is in the response
+    optional: bool,
+}
+
+/// The fields and named fragments a selection selects on one object type.
+///
+/// A fragment or spread behind a variable `@include`/`@skip` is a group, whose selections are in the
+/// response all together or not at all. Each group is recorded by the group it's nested in.
+#[derive(Default)]
+struct CollectedFields<'s> {
+    fields: Vec<(&'s str, CollectedField<'s>)>,
+    fragment_names: Vec<(&'s str, Option<usize>, bool)>,
+    groups: Vec<Option<usize>>,
+}
+
+impl CollectedFields<'_> {
+    fn is_within(&self, group: Option<usize>, ancestor: Option<usize>) -> bool {
+        let mut group = group;
+        loop {
+            if group == ancestor {
+                return true;
+            }
+            match group {
+                Some(g) => group = self.groups[g],
+                None => return false,
+            }
+        }
+    }
+
+    /// Merges a selection in `group` with an earlier one of the same name. The merged selection is in
+    /// the innermost group containing both, and is only certain there if either selection was.
+    fn merge(
+        &self,
+        (earlier_group, earlier_optional): (Option<usize>, bool),
+        (group, optional): (Option<usize>, bool),
+    ) -> (Option<usize>, bool) {
+        let mut common = earlier_group;
+        while !self.is_within(group, common) {
+            common = common.and_then(|g| self.groups[g]);
+        }
+        let certain =
+            (earlier_group == common && !earlier_optional) || (group == common && !optional);
+
+        (common, !certain)
+    }
 
-                write!(buffer, ", ")?;
+    /// Starts a group within `group` if the selection's presence depends on a variable
+    fn open_group(&mut self, group: Option<usize>, presence: Presence) -> Option<usize> {
+        if presence != Presence::Maybe {
+            return group;
+        }
+        self.groups.push(group);
+
+        Some(self.groups.len() - 1)
+    }
+
+    /// Joins the written fields, fragments and nested groups of `group` with `&`, leaving out nested
+    /// groups that select nothing. The top level always has an object, which starts with `typename`.
+    fn join_group(
+        &self,
+        group: Option<usize>,
+        typename: &str,
+        written_fields: &[String],
+        fragment_types: &[String],
+    ) -> Vec<String> {
+        let mut parts = vec![];
+        let fields = self
+            .fields
+            .iter()
+            .zip(written_fields)
+            .filter(|((_, field), _)| field.group == group)
+            .map(|(_, written)| written.as_str())
+            .collect::<String>();
+        if group.is_none() || !fields.is_empty() {
+            parts.push(format!("{{ {typename}{fields}}}"));
+        }
+        parts.extend(
+            self.fragment_names
+                .iter()
+                .zip(fragment_types)
+                .filter(|((_, fragment_group, _), _)| *fragment_group == group)
+                .map(|(_, fragment_type)| fragment_type.clone()),
+        );
+        for (nested, _) in self
+            .groups
+            .iter()
+            .enumerate()
+            .filter(|(_, parent)| **parent == group)
+        {
+            let nested_parts =
+                self.join_group(Some(nested), typename, written_fields, fragment_types);
+            if !nested_parts.is_empty() {
+                parts.push(format!("({} | {{}})", nested_parts.join(" & ")));
+            }
+        }
+
+        parts
+    }
+}
+
+/// Whether selections conditioned on `type_name` apply to `object`
+fn type_condition_applies(type_name: &str, object: &NamedType, ctx: &typescript::Context) -> bool {
+    let NamedType::Object {
+        name, interfaces, ..
+    } = object
+    else {
+        return false;
+    };
+
+    type_name == name
+        || interfaces.iter().any(|i| i.base_name() == type_name)
+        || matches!(
+            ctx.index.get(type_name),
+            Some(NamedType::Union { possible_types, .. })
+                if possible_types.iter().any(|t| t.base_name() == name)
+        )
+}
+
+/// Collects the selections that apply to `object` into `group`. A field behind a variable
+/// `@include`/`@skip` may be missing from the response, and a fragment behind one starts a new group.
+fn collect_fields<'s>(
+    selection_set: &'s SelectionSet<'static, String>,
+    object: &NamedType,
+    ctx: &'s typescript::Context,
+    group: Option<usize>,
+    collected: &mut CollectedFields<'s>,
+) -> Result<()> {
+    for selection in &selection_set.items {
+        let directives = match selection {
+            Selection::Field(SelectedField { directives, .. })
+            | Selection::FragmentSpread(FragmentSpread { directives, .. })
+            | Selection::InlineFragment(InlineFragment { directives, .. }) => directives,
+        };
+        let presence = Presence::of(directives);
+        if presence == Presence::Never {
+            continue;
+        }
+
+        match selection {
+            Selection::Field(field) => {
+                let optional = presence == Presence::Maybe;
+                let response_key = field.alias.as_ref().unwrap_or(&field.name).as_str();
+                match collected
+                    .fields
+                    .iter()
+                    .position(|(key, _)| *key == response_key)
+                {
+                    Some(index) => {
+                        let collected_field = &collected.fields[index].1;
+                        let (group, optional) = collected.merge(
+                            (collected_field.group, collected_field.optional),
+                            (group, optional),
+                        );
+                        let collected_field = &mut collected.fields[index].1;
+                        collected_field.selection_sets.push(&field.selection_set);
+                        collected_field.group = group;
+                        collected_field.optional = optional;
+                    }
+                    None => collected.fields.push((
+                        response_key,
+                        CollectedField {
+                            field,
+                            selection_sets: vec![&field.selection_set],
+                            group,
+                            optional,
+                        },
+                    )),
+                }
             }
             Selection::FragmentSpread(FragmentSpread {
-                position: _,
+                position,
                 fragment_name,
                 directives: _,
             }) => {
-                fragment_strings.push(format!(
-                    "{}Fragment{selection_set_suffix}",
-                    fragment_name.to_case(Case::Pascal),
-                    selection_set_suffix = ctx.options.selection_set_suffix
-                ));
+                let fragment = ctx.fragments.get(fragment_name).ok_or_else(|| {
+                    eyre!("Fragment \"{fragment_name}\" spread at {position} not found")
+                })?;
+                let TypeCondition::On(type_name) = &fragment.type_condition;
+                if type_name != object.name() && !type_condition_applies(type_name, object, ctx) {
+                    continue;
+                }
+                let group = collected.open_group(group, presence);
+                if type_name == object.name() {
+                    match collected
+                        .fragment_names
+                        .iter()
+                        .position(|(name, ..)| name == fragment_name)
+                    {
+                        Some(index) => {
+                            let (_, fragment_group, fragment_optional) =
+                                collected.fragment_names[index];
+                            let (group, optional) = collected
+                                .merge((fragment_group, fragment_optional), (group, false));
+                            collected.fragment_names[index] = (fragment_name, group, optional);
+                        }
+                        None => collected.fragment_names.push((fragment_name, group, false)),
+                    }
+                } else {
+                    // The fragment's own type would cover every possible type of its condition, so
+                    // only the fields that apply to this object are taken
+                    collect_fields(&fragment.selection_set, object, ctx, group, collected)?;
+                }
             }
             Selection::InlineFragment(InlineFragment {
-                position,
+                position: _,
                 type_condition,
                 directives: _,
                 selection_set,
             }) => {
-                if let Some(TypeCondition::On(type_name)) = type_condition {
-                    let mut fragment_buffer = String::new();
-                    recursively_typescriptify_selected_field(
-                        selection_set,
-                        &mut fragment_buffer,
-                        &TypeRef::from(ctx.index.get(type_name).ok_or_else(|| {
-                            eyre!("Type targetted by inline fragment at {position} not found")
-                        })?),
-                        ctx,
-                        &mut false,
+                let applies = match type_condition {
+                    Some(TypeCondition::On(type_name)) => {
+                        type_condition_applies(type_name, object, ctx)
+                    }
+                    None => true,
+                };
+                if applies {
+                    let group = collected.open_group(group, presence);
+                    collect_fields(selection_set, object, ctx, group, collected)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes the selection on a single object type. When `with_typename` is set, `__typename` is written
+/// as the object's name so a union of these objects can be narrowed.
+///
+/// Returns whether the written type needs parentheses to be the element type of an array.
+fn recursively_typescriptify_selected_object_fields(
+    selection_sets: &[&SelectionSet<'static, String>],
+    buffer: &mut String,
+    object: &NamedType,
+    ctx: &typescript::Context,
+    with_typename: bool,
+) -> Result<bool> {
+    let NamedType::Object {
+        name: object_name,
+        fields: selectable_fields,
+        ..
+    } = object
+    else {
+        return Err(eyre!("Expected \"{}\" to be an object type", object.name()));
+    };
+
+    let mut collected = CollectedFields::default();
+    for selection_set in selection_sets {
+        collect_fields(selection_set, object, ctx, None, &mut collected)?;
+    }
+
+    let mut written_fields = vec![];
+    let is_query_root = object_name == ctx.index.query.name();
+    for (
+        field_name,
+        CollectedField {
+            field: SelectedField { position, name, .. },
+            selection_sets,
+            optional,
+            ..
+        },
+    ) in &collected.fields
+    {
+        let mut buffer = String::new();
+        let meta_field_type = match name.as_str() {
+            "__typename" => {
+                if !(with_typename && *field_name == "__typename") {
+                    write!(
+                        buffer,
+                        "{field_name}{optional}: \"{object_name}\", ",
+                        optional = if *optional { "?" } else { "" }
                     )?;
-                    fragment_strings.push(fragment_buffer);
-                } else {
-                    return Err(eyre!(
-                        "Nameless/typeless inline fragments not (yet?) supported"
-                    ));
                 }
+                written_fields.push(buffer);
+                continue;
             }
+            "__schema" if is_query_root => Some(TypeRef::Container(TypeRefContainer::NonNull {
+                of_type: Box::new(TypeRef::To {
+                    name: "__Schema".to_owned(),
+                }),
+            })),
+            "__type" if is_query_root => Some(TypeRef::To {
+                name: "__Type".to_owned(),
+            }),
+            _ => None,
+        };
+
+        let of_type = match meta_field_type {
+            Some(of_type) => of_type,
+            None => {
+                let selected_field = selectable_fields
+                    .iter()
+                    .find(|f| f.name == **name)
+                    .ok_or_else(|| {
+                        eyre!("Tried to select non-existent field '{name}' at {position}")
+                    })?;
+
+                if let Some(tag) = deprecation_tag(
+                    selected_field.is_deprecated,
+                    selected_field.deprecation_reason.as_ref(),
+                ) {
+                    write!(buffer, "/** {tag} */ ")?;
+                }
+
+                selected_field.of_type.clone()
+            }
+        };
+
+        if *optional {
+            write!(buffer, "{field_name}?: ")?;
+        } else {
+            write!(buffer, "{field_name}: ")?;
         }
+
+        recursively_typescriptify_selected_field(selection_sets, &mut buffer, &of_type, ctx, true)?;
+
+        write!(buffer, ", ")?;
+        written_fields.push(buffer);
     }
-    write!(buffer, "}}")?;
 
-    if !fragment_strings.is_empty() {
-        write!(buffer, " & {}", fragment_strings.join(" & "))?;
+    let fragment_types = collected
+        .fragment_names
+        .iter()
+        .map(|(fragment_name, _, optional)| {
+            let fragment_type_name = format!(
+                "{}Fragment{selection_set_suffix}",
+                fragment_name.to_case(Case::Pascal),
+                selection_set_suffix = ctx.options.selection_set_suffix
+            );
+            ctx.reference(&fragment_type_name);
+            if *optional {
+                format!("Partial<{fragment_type_name}>")
+            } else {
+                fragment_type_name
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let typename = if with_typename {
+        format!("__typename: \"{object_name}\", ")
+    } else {
+        String::new()
+    };
+    let parts = collected.join_group(None, &typename, &written_fields, &fragment_types);
+    write!(buffer, "{}", parts.join(" & "))?;
+
+    Ok(parts.len() > 1)
+}
+
+/// Writes a selection on an interface or union as a union of the selection on each possible type
+fn recursively_typescriptify_selected_abstract_fields(
+    selection_sets: &[&SelectionSet<'static, String>],
+    buffer: &mut String,
+    abstract_type: &NamedType,
+    ctx: &typescript::Context,
+) -> Result<bool> {
+    let possible_types = ctx.index.possible_types(abstract_type)?;
+
+    if possible_types.is_empty() {
+        write!(buffer, "never")?;
+        return Ok(false);
     }
 
-    Ok(())
+    let members = possible_types
+        .into_iter()
+        .map(|object| {
+            let mut member = String::new();
+            recursively_typescriptify_selected_object_fields(
+                selection_sets,
+                &mut member,
+                object,
+                ctx,
+                true,
+            )?;
+            Ok(member)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    write!(buffer, "{}", members.join(" | "))?;
+
+    Ok(members.len() > 1 || members.first().is_some_and(|member| !member.ends_with('}')))
 }
 
+/// Writes `type_ref` wrapped in `Nullable` unless it's non-null. List items get their own nullability.
+///
+/// Returns whether the written type needs parentheses to be the element type of an array
 fn recursively_typescriptify_selected_field(
-    selection_set: &SelectionSet<'_, String>,
+    selection_sets: &[&SelectionSet<'static, String>],
     buffer: &mut String,
     type_ref: &TypeRef,
     ctx: &typescript::Context,
-    nullable: &mut bool,
-) -> Result<()> {
+    nullable: bool,
+) -> Result<bool> {
     let selected_field_type = ctx.index.type_from_ref(type_ref.clone())?;
     let mut local_buffer = String::new();
 
-    match selected_field_type {
+    let compound = match selected_field_type {
         Type::Named(named_type) => match named_type {
-            NamedType::Object { fields, .. } => {
-                recursively_typescriptify_selected_object_fields(
-                    selection_set,
+            object @ NamedType::Object { .. } => recursively_typescriptify_selected_object_fields(
+                selection_sets,
+                &mut local_buffer,
+                &object,
+                ctx,
+                false,
+            )?,
+            abstract_type @ (NamedType::Interface { .. } | NamedType::Union { .. }) => {
+                recursively_typescriptify_selected_abstract_fields(
+                    selection_sets,
                     &mut local_buffer,
-                    &fields,
+                    &abstract_type,
                     ctx,
-                )?;
+                )?
+            }
+            // Introspection enums aren't written out with the schema's types
+            NamedType::Enum {
+                name, enum_values, ..
+            } if name.starts_with("__") => {
+                let values = enum_values
+                    .iter()
+                    .map(|value| format!("\"{}\"", value.name))
+                    .collect::<Vec<_>>();
+                write!(local_buffer, "{}", values.join(" | "))?;
+                values.len() > 1
             }
             leaf_field_type => {
                 write!(
@@ -299,37 +907,44 @@ fn recursively_typescriptify_selected_field(
                     ctx.with(&TypeRef::from(&leaf_field_type))
                         .as_typescript_non_nullable()?
                 )?;
+                false
             }
         },
         Type::Container(contained) => match contained {
             TypeRefContainer::NonNull { of_type } => {
-                *nullable = false;
-                recursively_typescriptify_selected_field(
-                    selection_set,
-                    &mut local_buffer,
+                return recursively_typescriptify_selected_field(
+                    selection_sets,
+                    buffer,
                     &of_type,
                     ctx,
-                    nullable,
-                )?;
+                    false,
+                );
             }
             TypeRefContainer::List { of_type } => {
-                recursively_typescriptify_selected_field(
-                    selection_set,
-                    &mut local_buffer,
+                let mut element_buffer = String::new();
+                let compound = recursively_typescriptify_selected_field(
+                    selection_sets,
+                    &mut element_buffer,
                     &of_type,
                     ctx,
-                    nullable,
+                    true,
                 )?;
-                write!(local_buffer, "[]")?;
+                if compound {
+                    write!(local_buffer, "({element_buffer})[]")?;
+                } else {
+                    write!(local_buffer, "{element_buffer}[]")?;
+                }
+                false
             }
         },
     };
 
-    if *nullable {
+    if nullable {
+        ctx.reference("Nullable");
         write!(buffer, "Nullable<{local_buffer}>")?;
+        Ok(false)
     } else {
         write!(buffer, "{local_buffer}")?;
+        Ok(compound)
     }
-
-    Ok(())
 }
@@ -1,11 +1,14 @@
 use std::fmt::Write;
 
-use convert_case::{Case, Casing};
 use eyre::{eyre, Result};
 
-use super::{possibly_write_description, Typescriptable, TypescriptableWithBuffer, WithContext};
+use super::{
+    default_tag, deprecation_tag, possibly_write_description, possibly_write_doc_comment, Export,
+    Typescriptable, TypescriptableWithBuffer, WithContext,
+};
+use crate::app::config::{EnumStyle, ScalarType};
 use crate::gen::Buffer;
-use crate::graphql::schema::{NamedType, TypeRef, TypeRefContainer};
+use crate::graphql::schema::NamedType;
 use crate::util::{MaybeNamed, Named};
 
 impl NamedType {
@@ -33,25 +36,36 @@ impl<'a, 'b, 'c> TypescriptableWithBuffer for WithContext<'a, 'b, 'c, NamedType>
         if target.is_internal() {
             return Ok(());
         }
+        // Enums are imported as types too, since documents only refer to them in types
+        ctx.define(&ts_name, Export::Type);
         match target {
-            NamedType::Scalar { name, description } => {
+            NamedType::Scalar {
+                name, description, ..
+            } => {
                 possibly_write_description(&mut buffer.scalars, description.as_ref())?;
                 let scalar_type = match name.as_str() {
                     "ID" => r#"NewType<string, "ID">"#.to_owned(),
                     "String" => "string".to_owned(),
                     "Int" | "Float" => "number".to_owned(),
                     "Boolean" => "boolean".to_owned(),
-                    name => {
-                        let default = format!(r#"NewType<unknown, "{name}">"#);
-                        match &ctx.options.scalar_newtypes {
-                            None => default,
-                            Some(scalar_newtypes) => {
-                                scalar_newtypes.get(name).cloned().unwrap_or(default)
-                            }
-                        }
-                    }
+                    name => ctx
+                        .options
+                        .scalar_type(name)
+                        .and_then(ScalarType::output)
+                        .map_or_else(
+                            || format!(r#"NewType<unknown, "{name}">"#),
+                            ToOwned::to_owned,
+                        ),
                 };
                 writeln!(buffer.scalars, "export type {ts_name} = {scalar_type};")?;
+                if let Some(input_type) = ctx
+                    .options
+                    .scalar_type(name)
+                    .and_then(ScalarType::distinct_input)
+                {
+                    writeln!(buffer.scalars, "export type {ts_name}Input = {input_type};")?;
+                    ctx.define(&format!("{ts_name}Input"), Export::Type);
+                }
             }
             NamedType::Object {
                 name: _,
@@ -70,7 +84,11 @@ impl<'a, 'b, 'c> TypescriptableWithBuffer for WithContext<'a, 'b, 'c, NamedType>
                 }
                 writeln!(buffer.objects, "{{")?;
                 for f in fields {
-                    possibly_write_description(&mut buffer.objects, f.description.as_ref())?;
+                    possibly_write_doc_comment(
+                        &mut buffer.objects,
+                        f.description.as_ref(),
+                        deprecation_tag(f.is_deprecated, f.deprecation_reason.as_ref()).as_deref(),
+                    )?;
                     writeln!(
                         buffer.objects,
                         "  {}: {},",
@@ -99,7 +117,11 @@ impl<'a, 'b, 'c> TypescriptableWithBuffer for WithContext<'a, 'b, 'c, NamedType>
                 }
                 writeln!(buffer.interfaces, "{{")?;
                 for f in fields {
-                    possibly_write_description(&mut buffer.interfaces, f.description.as_ref())?;
+                    possibly_write_doc_comment(
+                        &mut buffer.interfaces,
+                        f.description.as_ref(),
+                        deprecation_tag(f.is_deprecated, f.deprecation_reason.as_ref()).as_deref(),
+                    )?;
                     writeln!(
                         buffer.interfaces,
                         "  {}: {},",
@@ -114,7 +136,7 @@ impl<'a, 'b, 'c> TypescriptableWithBuffer for WithContext<'a, 'b, 'c, NamedType>
                 description,
                 possible_types,
             } => {
-                possibly_write_description(&mut buffer.interfaces, description.as_ref())?;
+                possibly_write_description(&mut buffer.unions, description.as_ref())?;
                 let possible_types = possible_types
                     .iter()
                     .map(|t| {
@@ -131,40 +153,99 @@ impl<'a, 'b, 'c> TypescriptableWithBuffer for WithContext<'a, 'b, 'c, NamedType>
                 enum_values,
             } => {
                 possibly_write_description(&mut buffer.enums, description.as_ref())?;
-                writeln!(buffer.enums, "export enum {ts_name} {{")?;
-                for v in enum_values {
-                    possibly_write_description(&mut buffer.enums, v.description.as_ref())?;
-                    writeln!(
-                        buffer.enums,
-                        "  {} = \"{}\",",
-                        v.name.to_case(Case::Pascal),
-                        v.name
-                    )?;
+                let member_case = ctx.options.enum_member_case;
+                match ctx.options.enum_style {
+                    EnumStyle::Enum | EnumStyle::ConstEnum => {
+                        let keyword = if ctx.options.enum_style == EnumStyle::ConstEnum {
+                            "const enum"
+                        } else {
+                            "enum"
+                        };
+                        writeln!(buffer.enums, "export {keyword} {ts_name} {{")?;
+                        for v in enum_values {
+                            possibly_write_doc_comment(
+                                &mut buffer.enums,
+                                v.description.as_ref(),
+                                deprecation_tag(v.is_deprecated, v.deprecation_reason.as_ref())
+                                    .as_deref(),
+                            )?;
+                            writeln!(
+                                buffer.enums,
+                                "  {} = \"{}\",",
+                                member_case.apply(&v.name),
+                                v.name
+                            )?;
+                        }
+                        writeln!(buffer.enums, "}}")?;
+                    }
+                    // An empty union would be a syntax error
+                    EnumStyle::Union if enum_values.is_empty() => {
+                        writeln!(buffer.enums, "export type {ts_name} = never;")?;
+                    }
+                    EnumStyle::Union => {
+                        writeln!(buffer.enums, "export type {ts_name} =")?;
+                        for v in enum_values {
+                            possibly_write_doc_comment(
+                                &mut buffer.enums,
+                                v.description.as_ref(),
+                                deprecation_tag(v.is_deprecated, v.deprecation_reason.as_ref())
+                                    .as_deref(),
+                            )?;
+                            writeln!(buffer.enums, "  | \"{}\"", v.name)?;
+                        }
+                        writeln!(buffer.enums, ";")?;
+                    }
+                    EnumStyle::ConstObject => {
+                        writeln!(buffer.enums, "export const {ts_name} = {{")?;
+                        for v in enum_values {
+                            possibly_write_doc_comment(
+                                &mut buffer.enums,
+                                v.description.as_ref(),
+                                deprecation_tag(v.is_deprecated, v.deprecation_reason.as_ref())
+                                    .as_deref(),
+                            )?;
+                            writeln!(
+                                buffer.enums,
+                                "  {}: \"{}\",",
+                                member_case.apply(&v.name),
+                                v.name
+                            )?;
+                        }
+                        writeln!(buffer.enums, "}} as const;")?;
+                        writeln!(
+                            buffer.enums,
+                            "export type {ts_name} = (typeof {ts_name})[keyof typeof {ts_name}];"
+                        )?;
+                    }
                 }
-                writeln!(buffer.enums, "}}")?;
             }
             NamedType::InputObject {
                 name: _,
                 description,
                 input_fields,
             } => {
-                possibly_write_description(&mut buffer.objects, description.as_ref())?;
+                possibly_write_description(&mut buffer.input_objects, description.as_ref())?;
                 writeln!(buffer.input_objects, "export type {ts_name} = {{")?;
                 for f in input_fields {
-                    possibly_write_description(&mut buffer.input_objects, f.description.as_ref())?;
-                    if let TypeRef::Container(TypeRefContainer::NonNull { .. }) = f.of_type {
+                    possibly_write_doc_comment(
+                        &mut buffer.input_objects,
+                        f.description.as_ref(),
+                        default_tag(f.default_value.clone()).as_deref(),
+                    )?;
+                    // A field with a default can be left out, but still can't be null
+                    if f.of_type.is_non_null() && f.default_value.is_none() {
                         writeln!(
                             buffer.input_objects,
                             "  {}: {},",
                             f.name,
-                            ctx.with(&f.of_type).as_typescript()?
+                            ctx.with(&f.of_type).as_input_typescript()?
                         )?;
                     } else {
                         writeln!(
                             buffer.input_objects,
                             "  {}?: {},",
                             f.name,
-                            ctx.with(&f.of_type).as_typescript()?
+                            ctx.with(&f.of_type).as_input_typescript()?
                         )?;
                     }
                 }
@@ -1,29 +1,63 @@
 pub mod definition;
 pub mod graphql_type;
+pub mod react_hooks;
+pub mod sdk;
 pub mod type_ref;
+pub mod zod;
 
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fmt::Write;
 
 use eyre::{eyre, Result};
+use graphql_parser::query::{Definition, Document, FragmentDefinition};
 
 use crate::app::config::TypescriptOptions;
 use crate::gen::Buffer;
-use crate::graphql::schema::{NamedType, Schema, Type, TypeRef};
+use crate::graphql::schema::{introspection_types, NamedType, Schema, Type, TypeRef};
 use crate::{typescript, util::Named};
 
 pub(in crate::typescript) fn possibly_write_description<W: Write>(
     out: &mut W,
     description: Option<&String>,
 ) -> Result<()> {
-    if let Some(description) = description {
-        if description.contains('\n') {
-            writeln!(out, "/**\n * {}\n */", description.replace('\n', "\n * "))?;
-        } else {
-            writeln!(out, "/** {description} */")?;
-        }
+    possibly_write_doc_comment(out, description, None)
+}
+
+/// The JSDoc tag for a deprecated field or enum value, e.g. `@deprecated Use foo instead`
+pub(in crate::typescript) fn deprecation_tag(
+    is_deprecated: bool,
+    deprecation_reason: Option<&String>,
+) -> Option<String> {
+    is_deprecated.then(|| match deprecation_reason {
+        Some(reason) => format!("@deprecated {reason}"),
+        None => "@deprecated".to_owned(),
+    })
+}
+
+/// The JSDoc tag for an argument or input field with a default, e.g. `@default 10`
+pub(in crate::typescript) fn default_tag(default_value: Option<String>) -> Option<String> {
+    default_value.map(|value| format!("@default {value}"))
+}
+
+pub(in crate::typescript) fn possibly_write_doc_comment<W: Write>(
+    out: &mut W,
+    description: Option<&String>,
+    tag: Option<&str>,
+) -> Result<()> {
+    let comment = match (description, tag) {
+        (None, None) => return Ok(()),
+        (Some(description), None) => description.clone(),
+        (None, Some(tag)) => tag.to_owned(),
+        (Some(description), Some(tag)) => format!("{description}\n{tag}"),
     };
 
+    if comment.contains('\n') {
+        writeln!(out, "/**\n * {}\n */", comment.replace('\n', "\n * "))?;
+    } else {
+        writeln!(out, "/** {comment} */")?;
+    }
+
     Ok(())
 }
 
@@ -55,11 +89,67 @@ impl<'a> TypeIndex<'a> {
         Ok(t)
     }
 
+    /// The object types a selection on `named_type` may resolve to. Interfaces parsed from SDL don't
+    /// list their possible types, so implementations are looked up too.
+    pub fn possible_types(&self, named_type: &NamedType) -> Result<Vec<&NamedType>> {
+        let names = match named_type {
+            NamedType::Union { possible_types, .. } => possible_types
+                .iter()
+                .map(|t| t.base_name().to_owned())
+                .collect::<Vec<_>>(),
+            NamedType::Interface {
+                name,
+                possible_types,
+                ..
+            } => {
+                let mut implementations = self
+                    .map
+                    .values()
+                    .filter_map(|t| match t {
+                        NamedType::Object {
+                            name: object_name,
+                            interfaces,
+                            ..
+                        } if interfaces.iter().any(|i| i.base_name() == name) => {
+                            Some(object_name.clone())
+                        }
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+                implementations.sort();
+
+                let mut names = possible_types
+                    .iter()
+                    .map(|t| t.base_name().to_owned())
+                    .collect::<Vec<_>>();
+                for implementation in implementations {
+                    if !names.contains(&implementation) {
+                        names.push(implementation);
+                    }
+                }
+                names
+            }
+            other => vec![other.name().to_owned()],
+        };
+
+        names
+            .iter()
+            .map(|name| {
+                self.get(name)
+                    .ok_or_else(|| eyre!("Possible type \"{name}\" not found in TypeIndex"))
+            })
+            .collect()
+    }
+
     pub fn try_new(schema: &'a Schema) -> Result<Self> {
         let mut map = schema.types.iter().fold(HashMap::new(), |mut map, t| {
             map.insert(t.name().to_owned(), t);
             map
         });
+        // Selections on `__schema` and `__type` need these even if the schema doesn't declare them
+        for t in introspection_types() {
+            map.entry(t.name().to_owned()).or_insert(t);
+        }
         let query = map
             .remove(&schema.query_type.name)
             .ok_or_else(|| eyre!("TypeIndex has no query type"))?;
@@ -82,7 +172,58 @@ impl<'a> TypeIndex<'a> {
 
 pub struct Context<'a> {
     pub index: TypeIndex<'a>,
-    pub options: TypescriptOptions,
+    pub options: &'a TypescriptOptions,
+    /// Every fragment in the documents, by name, so spreads can be looked up wherever they're used
+    pub fragments: HashMap<String, FragmentDefinition<'static, String>>,
+    /// Names written so far that are defined elsewhere in the generated code, so a module written on
+    /// its own knows what to import
+    pub referenced: RefCell<BTreeSet<String>>,
+    /// Names exported so far, so a module written on its own is known to be where they're imported
+    /// from
+    pub defined: RefCell<BTreeMap<String, Export>>,
+}
+
+/// How another module imports an export
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Export {
+    /// Only used as a type, so imported with `import type`
+    Type,
+    /// Used at runtime, e.g. a zod schema
+    Value,
+}
+
+impl<'a> Context<'a> {
+    /// Records that `name` was written as a reference to a type or schema defined elsewhere
+    pub fn reference(&self, name: &str) {
+        self.referenced.borrow_mut().insert(name.to_owned());
+    }
+
+    pub fn take_references(&self) -> BTreeSet<String> {
+        self.referenced.take()
+    }
+
+    /// Records that `name` was written as an export
+    pub fn define(&self, name: &str, export: Export) {
+        self.defined.borrow_mut().insert(name.to_owned(), export);
+    }
+
+    pub fn take_definitions(&self) -> BTreeMap<String, Export> {
+        self.defined.take()
+    }
+}
+
+/// Collects the fragment definitions of a document by name
+pub fn fragments_by_name(
+    document: &Document<'static, String>,
+) -> HashMap<String, FragmentDefinition<'static, String>> {
+    document
+        .definitions
+        .iter()
+        .filter_map(|definition| match definition {
+            Definition::Fragment(fragment) => Some((fragment.name.clone(), fragment.clone())),
+            Definition::Operation(_) => None,
+        })
+        .collect()
 }
 
 pub struct WithContext<'a, 'b, 'c, T> {
@@ -102,6 +243,9 @@ pub trait Typescriptable {
     fn as_typescript_non_nullable(&self) -> Result<String> {
         unimplemented!()
     }
+
+    /// The type as written in arguments and input objects, where scalars may accept a different type
+    fn as_input_typescript(&self) -> Result<String>;
 }
 
 pub(crate) trait TypescriptableWithBuffer {
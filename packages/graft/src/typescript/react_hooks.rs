@@ -0,0 +1,172 @@
+use std::fmt::Write;
+
+use eyre::Result;
+
+use super::{Context, Export};
+use crate::app::config::ReactHooksLibrary;
+use crate::gen::Buffer;
+
+/// The generated names of an operation that its hooks are typed with
+pub(crate) struct HookedOperation<'a> {
+    pub name: &'a str,
+    /// `Query`, `Mutation` or `Subscription`
+    pub operation_type_name: &'a str,
+    pub document_name: &'a str,
+    pub selection_set_name: &'a str,
+    pub args_name: &'a str,
+    pub variables_optional: bool,
+}
+
+/// Imports the hooks' library, if any hooks were written
+pub(crate) fn write_import(ctx: &Context, buffer: &mut Buffer) -> Result<()> {
+    if buffer.react_hooks.is_empty() {
+        return Ok(());
+    }
+
+    match ctx.options.react_hooks {
+        Some(ReactHooksLibrary::Apollo) => {
+            writeln!(
+                buffer.imports,
+                r#"import * as Apollo from "@apollo/client";"#
+            )?;
+        }
+        Some(ReactHooksLibrary::Urql) => {
+            writeln!(buffer.imports, r#"import * as Urql from "urql";"#)?;
+        }
+        None => {}
+    }
+
+    Ok(())
+}
+
+/// Writes `use{Name}Query` and `use{Name}LazyQuery` for a query, or `use{Name}Mutation` or
+/// `use{Name}Subscription`
+pub(crate) fn write_hooks(
+    ctx: &Context,
+    library: ReactHooksLibrary,
+    operation: &HookedOperation,
+    out: &mut String,
+) -> Result<()> {
+    let HookedOperation {
+        name,
+        operation_type_name,
+        ..
+    } = operation;
+    ctx.define(&format!("use{name}{operation_type_name}"), Export::Value);
+    if *operation_type_name == "Query" {
+        ctx.define(&format!("use{name}LazyQuery"), Export::Value);
+    }
+
+    match library {
+        ReactHooksLibrary::Apollo => write_apollo_hooks(operation, out),
+        ReactHooksLibrary::Urql => write_urql_hooks(operation, out),
+    }
+}
+
+fn write_apollo_hooks(operation: &HookedOperation, out: &mut String) -> Result<()> {
+    let HookedOperation {
+        name,
+        operation_type_name,
+        document_name,
+        selection_set_name,
+        args_name,
+        variables_optional,
+    } = operation;
+    let generics = format!("{selection_set_name}, {args_name}");
+    // Hooks that send the operation straight away need its required variables, whereas a mutation
+    // is given them when it's called
+    let options = if *variables_optional || *operation_type_name == "Mutation" {
+        format!("options?: Apollo.{operation_type_name}HookOptions<{generics}>")
+    } else {
+        format!(
+            "options: Apollo.{operation_type_name}HookOptions<{generics}> & {{ variables: {args_name} }}"
+        )
+    };
+
+    writeln!(
+        out,
+        "export function use{name}{operation_type_name}({options}) {{"
+    )?;
+    writeln!(
+        out,
+        "  return Apollo.use{operation_type_name}<{generics}>({document_name}, options);"
+    )?;
+    writeln!(out, "}}")?;
+
+    if *operation_type_name == "Query" {
+        writeln!(
+            out,
+            "export function use{name}LazyQuery(options?: Apollo.LazyQueryHookOptions<{generics}>) {{"
+        )?;
+        writeln!(
+            out,
+            "  return Apollo.useLazyQuery<{generics}>({document_name}, options);"
+        )?;
+        writeln!(out, "}}")?;
+    }
+
+    Ok(())
+}
+
+fn write_urql_hooks(operation: &HookedOperation, out: &mut String) -> Result<()> {
+    let HookedOperation {
+        name,
+        operation_type_name,
+        document_name,
+        selection_set_name,
+        args_name,
+        variables_optional,
+    } = operation;
+    let options_parameter = if *variables_optional {
+        "options?"
+    } else {
+        "options"
+    };
+
+    match *operation_type_name {
+        "Query" => {
+            let query_args = format!("Urql.UseQueryArgs<{args_name}, {selection_set_name}>");
+            writeln!(
+                out,
+                r#"export function use{name}Query({options_parameter}: Omit<{query_args}, "query">) {{"#
+            )?;
+            writeln!(
+                out,
+                "  return Urql.useQuery<{selection_set_name}, {args_name}>({{ query: {document_name}, ...options }});"
+            )?;
+            writeln!(out, "}}")?;
+            // urql has no lazy hook, but a paused query only runs when it's reexecuted. It's still
+            // given its variables up front, so they're required the same as for `use{Name}Query`.
+            writeln!(
+                out,
+                r#"export function use{name}LazyQuery({options_parameter}: Omit<{query_args}, "query" | "pause">) {{"#
+            )?;
+            writeln!(
+                out,
+                "  return Urql.useQuery<{selection_set_name}, {args_name}>({{ query: {document_name}, ...options, pause: true }});"
+            )?;
+            writeln!(out, "}}")?;
+        }
+        "Mutation" => {
+            writeln!(out, "export function use{name}Mutation() {{")?;
+            writeln!(
+                out,
+                "  return Urql.useMutation<{selection_set_name}, {args_name}>({document_name});"
+            )?;
+            writeln!(out, "}}")?;
+        }
+        _ => {
+            writeln!(
+                out,
+                r#"export function use{name}Subscription<R = {selection_set_name}>({options_parameter}: Omit<Urql.UseSubscriptionArgs<{args_name}, {selection_set_name}>, "query">, handler?: Urql.SubscriptionHandler<{selection_set_name}, R>) {{"#
+            )?;
+            writeln!(
+                out,
+                "  return Urql.useSubscription<{selection_set_name}, R, {args_name}>({{ query: {document_name}, ...options }}, handler);"
+            )?;
+            writeln!(out, "}}")?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use std::fmt::Write;
+
+use eyre::Result;
+
+use super::{Context, Export};
+use crate::gen::Buffer;
+
+/// Writes the `Requester` type, which sends a document with its variables, so that `getSdk` works
+/// with `fetch`, `graphql-request` or any other client. Subscriptions yield many results, so they're
+/// sent by a `Subscriber` instead.
+pub(crate) fn write_requester(ctx: &Context, buffer: &mut Buffer) -> Result<()> {
+    let document_type_name = ctx.options.document_import.type_name();
+    writeln!(
+        buffer.util_types,
+        "export type Requester<C = {{}}> = <R, V>(document: {document_type_name}<R, V>, variables?: V, options?: C) => Promise<R>;"
+    )?;
+    writeln!(
+        buffer.util_types,
+        "export type Subscriber<C = {{}}> = <R, V>(document: {document_type_name}<R, V>, variables?: V, options?: C) => AsyncIterable<R>;"
+    )?;
+    ctx.define("Requester", Export::Type);
+    ctx.define("Subscriber", Export::Type);
+
+    Ok(())
+}
+
+/// Writes a method that sends the operation, to `buffer.sdk_subscriptions` for a subscription or
+/// `buffer.sdk` otherwise
+pub(crate) fn write_method(
+    method_name: &str,
+    document_name: &str,
+    selection_set_name: &str,
+    args_name: &str,
+    variables_optional: bool,
+    subscription: bool,
+    buffer: &mut Buffer,
+) -> Result<()> {
+    let variables = if variables_optional {
+        "variables?"
+    } else {
+        "variables"
+    };
+    let (out, result, sender) = if subscription {
+        (&mut buffer.sdk_subscriptions, "AsyncIterable", "subscriber")
+    } else {
+        (&mut buffer.sdk, "Promise", "requester")
+    };
+    writeln!(
+        out,
+        "    {method_name}({variables}: {args_name}, options?: C): {result}<{selection_set_name}> {{"
+    )?;
+    writeln!(
+        out,
+        "      return {sender}({document_name}, variables, options);"
+    )?;
+    writeln!(out, "    }},")?;
+
+    Ok(())
+}
+
+/// Wraps the methods written so far in `getSdk`, if there are any. It's only given a `Subscriber`
+/// if there are subscriptions.
+pub(crate) fn wrap_methods(ctx: &Context, buffer: &mut Buffer) -> Result<()> {
+    if buffer.sdk.is_empty() && buffer.sdk_subscriptions.is_empty() {
+        return Ok(());
+    }
+
+    ctx.reference("Requester");
+    let mut parameters = "requester: Requester<C>".to_owned();
+    if !buffer.sdk_subscriptions.is_empty() {
+        ctx.reference("Subscriber");
+        parameters.push_str(", subscriber: Subscriber<C>");
+    }
+    let mut sdk = String::new();
+    writeln!(sdk, "export function getSdk<C = {{}}>({parameters}) {{")?;
+    writeln!(sdk, "  return {{")?;
+    write!(sdk, "{}", buffer.sdk)?;
+    write!(sdk, "{}", std::mem::take(&mut buffer.sdk_subscriptions))?;
+    writeln!(sdk, "  }};")?;
+    writeln!(sdk, "}}")?;
+    writeln!(sdk, "export type Sdk = ReturnType<typeof getSdk>;")?;
+    ctx.define("getSdk", Export::Value);
+    ctx.define("Sdk", Export::Type);
+    buffer.sdk = sdk;
+
+    Ok(())
+}
@@ -1,37 +1,61 @@
 use eyre::Result;
 
 use super::{Typescriptable, WithContext};
-use crate::graphql::schema::{Type, TypeRef, TypeRefContainer};
+use crate::app::config::ScalarType;
+use crate::graphql::schema::{NamedType, Type, TypeRef, TypeRefContainer};
+use crate::util::Named;
 
 impl<'a, 'b, 'c> Typescriptable for WithContext<'a, 'b, 'c, TypeRef> {
     fn as_typescript(&self) -> Result<String> {
-        recursive_typescriptify(self, &mut true)
+        recursive_typescriptify(self, true, false)
     }
 
     fn as_typescript_non_nullable(&self) -> Result<String> {
-        recursive_typescriptify(self, &mut false)
+        recursive_typescriptify(self, false, false)
+    }
+
+    fn as_input_typescript(&self) -> Result<String> {
+        recursive_typescriptify(self, true, true)
     }
 }
 
+/// `nullable` applies to this level only, since each list item has its own nullability
 fn recursive_typescriptify(
     with_context: &WithContext<'_, '_, '_, TypeRef>,
-    nullable: &mut bool,
+    nullable: bool,
+    input: bool,
 ) -> Result<String> {
     let WithContext { target, ctx } = with_context;
     let this_type = ctx.index.type_from_ref((*target).clone())?;
     let type_name = match this_type {
         Type::Container(TypeRefContainer::NonNull { of_type }) => {
-            *nullable = false;
-            recursive_typescriptify(&ctx.with(&of_type), nullable)?
+            return recursive_typescriptify(&ctx.with(&of_type), false, input);
         }
         Type::Container(TypeRefContainer::List { of_type }) => {
-            let string = recursive_typescriptify(&ctx.with(&of_type), nullable)?;
+            let string = recursive_typescriptify(&ctx.with(&of_type), true, input)?;
             format!("{string}[]")
         }
-        Type::Named(other_type) => other_type.typescript_name(),
+        Type::Named(scalar @ NamedType::Scalar { .. })
+            if input
+                && ctx
+                    .options
+                    .scalar_type(scalar.name())
+                    .and_then(ScalarType::distinct_input)
+                    .is_some() =>
+        {
+            let type_name = format!("{}Input", scalar.typescript_name());
+            ctx.reference(&type_name);
+            type_name
+        }
+        Type::Named(other_type) => {
+            let type_name = other_type.typescript_name();
+            ctx.reference(&type_name);
+            type_name
+        }
     };
 
-    let type_name = if *nullable {
+    let type_name = if nullable {
+        ctx.reference("Nullable");
         format!("Nullable<{type_name}>")
     } else {
         type_name
@@ -0,0 +1,194 @@
+use std::fmt::Write;
+
+use eyre::Result;
+use graphql_parser::query::VariableDefinition;
+
+use super::{Context, Export};
+use crate::app::config::{EnumStyle, ScalarType};
+use crate::gen::Buffer;
+use crate::graphql::schema::{NamedType, Schema, Type, TypeRef, TypeRefContainer};
+
+pub fn schema_name(ts_name: &str) -> String {
+    format!("{ts_name}Schema")
+}
+
+/// Writes a schema per scalar, enum and input object. Scalars and enums come first, since input objects
+/// refer to them directly, whereas input objects refer to each other lazily.
+pub(crate) fn write_schemas(ctx: &Context, schema: &Schema, buffer: &mut Buffer) -> Result<()> {
+    writeln!(
+        buffer.util_types,
+        "export type ZodSchema<T> = z.ZodType<T, z.ZodTypeDef, unknown>;"
+    )?;
+    ctx.define("ZodSchema", Export::Type);
+
+    let types = schema.types.iter().filter(|t| !t.is_internal());
+
+    for t in types.clone() {
+        if let NamedType::Scalar { name, .. } = t {
+            write_scalar_schema(ctx, name, &t.typescript_name(), &mut buffer.zod_schemas)?;
+        }
+    }
+
+    for t in types.clone() {
+        if let NamedType::Enum { enum_values, .. } = t {
+            let ts_name = t.typescript_name();
+            let values = enum_values
+                .iter()
+                .map(|v| format!("\"{}\"", v.name))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let expression = match ctx.options.enum_style {
+                EnumStyle::Enum | EnumStyle::ConstObject => format!("z.nativeEnum({ts_name})"),
+                EnumStyle::Union => format!("z.enum([{values}])"),
+                // A const enum has no object at runtime to hand to `z.nativeEnum`
+                EnumStyle::ConstEnum => format!(
+                    "z.custom<{ts_name}>((value) => typeof value === \"string\" && [{values}].includes(value))"
+                ),
+            };
+            let schema_name = schema_name(&ts_name);
+            writeln!(
+                buffer.zod_schemas,
+                "export const {schema_name}: ZodSchema<{ts_name}> = {expression};"
+            )?;
+            ctx.define(&schema_name, Export::Value);
+        }
+    }
+
+    for t in types {
+        if let NamedType::InputObject { input_fields, .. } = t {
+            let ts_name = t.typescript_name();
+            let fields = input_fields
+                .iter()
+                .map(|f| {
+                    (
+                        f.name.as_str(),
+                        f.of_type.clone(),
+                        f.default_value.is_some(),
+                    )
+                })
+                .collect::<Vec<_>>();
+            write_object_schema(ctx, &ts_name, fields, &mut buffer.zod_schemas)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_scalar_schema(ctx: &Context, name: &str, ts_name: &str, out: &mut String) -> Result<()> {
+    let scalar_type = ctx.options.scalar_type(name);
+    // Schemas validate what's sent, so they check a scalar's input type where it has one
+    let checked_type = match scalar_type.and_then(ScalarType::distinct_input) {
+        Some(_) => format!("{ts_name}Input"),
+        None => ts_name.to_owned(),
+    };
+    let expression = match (name, scalar_type.and_then(ScalarType::zod)) {
+        (_, Some(zod)) => zod.to_owned(),
+        ("ID", None) => {
+            format!("z.custom<{checked_type}>((value) => typeof value === \"string\")")
+        }
+        ("String", None) => "z.string()".to_owned(),
+        ("Int", None) => "z.number().int()".to_owned(),
+        ("Float", None) => "z.number()".to_owned(),
+        ("Boolean", None) => "z.boolean()".to_owned(),
+        (_, None) => format!("z.custom<{checked_type}>()"),
+    };
+    let schema_name = schema_name(ts_name);
+    writeln!(
+        out,
+        "export const {schema_name}: ZodSchema<{checked_type}> = {expression};"
+    )?;
+    ctx.define(&schema_name, Export::Value);
+
+    Ok(())
+}
+
+/// Writes the schema of an operation's `Args` type
+pub(crate) fn write_args_schema(
+    ctx: &Context,
+    args_name: &str,
+    variable_definitions: &[VariableDefinition<'static, String>],
+    out: &mut String,
+) -> Result<()> {
+    ctx.reference("ZodSchema");
+    let fields = variable_definitions
+        .iter()
+        .map(|def| {
+            (
+                def.name.as_str(),
+                TypeRef::from(def.var_type.clone()),
+                def.default_value.is_some(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    write_object_schema(ctx, args_name, fields, out)
+}
+
+/// A field may be left out if it's nullable or has a default, matching `?:` in the Typescript type.
+/// Objects are strict, since a server rejects fields its input types don't have.
+fn write_object_schema(
+    ctx: &Context,
+    ts_name: &str,
+    fields: Vec<(&str, TypeRef, bool)>,
+    out: &mut String,
+) -> Result<()> {
+    let schema_name = schema_name(ts_name);
+    ctx.define(&schema_name, Export::Value);
+    if fields.is_empty() {
+        writeln!(
+            out,
+            "export const {schema_name}: ZodSchema<{ts_name}> = z.object({{}}).strict();"
+        )?;
+        return Ok(());
+    }
+
+    writeln!(
+        out,
+        "export const {schema_name}: ZodSchema<{ts_name}> = z.object({{"
+    )?;
+    for (name, of_type, has_default) in fields {
+        let optional = !of_type.is_non_null() || has_default;
+        let expression = recursive_zodify(ctx, of_type, true, optional)?;
+        writeln!(out, "  {name}: {expression},")?;
+    }
+    writeln!(out, "}}).strict();")?;
+
+    Ok(())
+}
+
+/// `nullable` applies to this level only, since each list item has its own nullability, whereas
+/// `optional` is whether the field itself may be left out
+fn recursive_zodify(
+    ctx: &Context,
+    type_ref: TypeRef,
+    nullable: bool,
+    optional: bool,
+) -> Result<String> {
+    let expression = match ctx.index.type_from_ref(type_ref)? {
+        Type::Container(TypeRefContainer::NonNull { of_type }) => {
+            return recursive_zodify(ctx, *of_type, false, optional);
+        }
+        Type::Container(TypeRefContainer::List { of_type }) => {
+            format!("z.array({})", recursive_zodify(ctx, *of_type, true, false)?)
+        }
+        Type::Named(input_object @ NamedType::InputObject { .. }) => {
+            let schema_name = schema_name(&input_object.typescript_name());
+            ctx.reference(&schema_name);
+            format!("z.lazy(() => {schema_name})")
+        }
+        Type::Named(other_type) => {
+            let schema_name = schema_name(&other_type.typescript_name());
+            ctx.reference(&schema_name);
+            schema_name
+        }
+    };
+
+    let expression = match (nullable, optional) {
+        (true, true) => format!("{expression}.nullish()"),
+        (true, false) => format!("{expression}.nullable()"),
+        (false, true) => format!("{expression}.optional()"),
+        (false, false) => expression,
+    };
+
+    Ok(expression)
+}
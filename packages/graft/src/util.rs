@@ -1,7 +1,9 @@
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
 use ::graphql_parser::query::Type as GraphQLParserType;
+use eyre::Result;
 
+use crate::cross;
 use crate::graphql::schema::{NamedType, TypeRef};
 
 pub type Arg<'a> = GraphQLParserType<'a, &'a str>;
@@ -54,6 +56,55 @@ pub fn path_with_possible_prefix(prefix: Option<&Path>, path: &Path) -> PathBuf
     prefix.map_or_else(|| PathBuf::from(path), |p| p.join(path))
 }
 
+/// Where the module generated for a document file goes, e.g. `Foo.graphql` -> `Foo.generated.ts`
+pub fn near_operation_file_path(document_path: &Path) -> PathBuf {
+    document_path.with_extension("generated.ts")
+}
+
+/// Resolves `.` and `..` components without touching the filesystem
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if normalized.file_name().is_some() => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// The specifier a Typescript module at `from` would use to import the module at `to`, e.g. `../types`.
+/// Relative paths are taken from the current directory, so either path may be absolute.
+pub fn relative_module_path(from: &Path, to: &Path) -> Result<String> {
+    let current_dir = cross::env::current_dir()?;
+    let from_dir = normalize(&current_dir.join(from.parent().unwrap_or(Path::new(""))));
+    let to = normalize(&current_dir.join(to.with_extension("")));
+
+    let from_components = from_dir.components().collect::<Vec<_>>();
+    let to_components = to.components().collect::<Vec<_>>();
+    let common = from_components
+        .iter()
+        .zip(&to_components)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut relative = PathBuf::new();
+    for _ in common..from_components.len() {
+        relative.push("..");
+    }
+    relative.extend(&to_components[common..]);
+
+    let relative = relative.to_string_lossy().replace('\\', "/");
+    if relative.starts_with("../") {
+        Ok(relative)
+    } else {
+        Ok(format!("./{relative}"))
+    }
+}
+
 #[cfg(feature = "debug")]
 pub mod debug {
     use eyre::{eyre, Result};
@@ -100,3 +151,71 @@ macro_rules! debug_log {
         $crate::util::debug::log(&format_args!($($t)*).to_string())
     }
 }
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{near_operation_file_path, relative_module_path};
+
+    #[test]
+    fn relative_module_paths() {
+        let cases = [
+            ("src/a.ts", "src/b.ts", "./b"),
+            ("src/a.ts", "src/types/index.ts", "./types/index"),
+            ("src/deep/a.ts", "src/types.ts", "../types"),
+            ("./src/a/b.ts", "lib/c.ts", "../../lib/c"),
+            (
+                "src/Foo.generated.ts",
+                "src/Bar.generated.ts",
+                "./Bar.generated",
+            ),
+        ];
+
+        for (from, to, expected) in cases {
+            assert_eq!(
+                expected,
+                relative_module_path(Path::new(from), Path::new(to)).expect("relative path"),
+                "{from} -> {to}"
+            );
+        }
+    }
+
+    #[test]
+    fn relative_module_paths_between_absolute_and_relative() {
+        let current_dir = std::env::current_dir().expect("current dir");
+
+        assert_eq!(
+            "./types",
+            relative_module_path(&current_dir.join("src/a.ts"), Path::new("src/types.ts"))
+                .expect("relative path")
+        );
+        assert_eq!(
+            "../types",
+            relative_module_path(
+                Path::new("src/deep/a.ts"),
+                &current_dir.join("src/types.ts")
+            )
+            .expect("relative path")
+        );
+
+        let parent = current_dir
+            .file_name()
+            .and_then(|name| name.to_str())
+            .expect("current dir name");
+        assert_eq!(
+            format!("./{parent}/types"),
+            relative_module_path(Path::new("../a.ts"), Path::new("types.ts"))
+                .expect("relative path")
+        );
+    }
+
+    #[test]
+    fn near_operation_file_paths() {
+        assert_eq!(
+            Path::new("src/Foo.generated.ts"),
+            near_operation_file_path(Path::new("src/Foo.graphql"))
+        );
+    }
+}
@@ -0,0 +1,219 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use eyre::Result;
+
+use crate::app::{
+    self, cli,
+    config::{GenPlans, SchemaSource},
+    Config,
+};
+use crate::output::Output;
+use crate::{cross, cross_eprintln, cross_println, print_info};
+
+const POLL_INTERVAL_MILLIS: u64 = 300;
+
+/// Modification times of every file a `generates` entry reads, or the error hit while finding them
+type EntrySnapshot = Result<BTreeMap<PathBuf, Option<u128>>, String>;
+
+#[derive(PartialEq)]
+struct Snapshot {
+    config: Option<u128>,
+    entries: BTreeMap<String, EntrySnapshot>,
+}
+
+fn watched_paths(ctx: &app::Context, plans: &GenPlans) -> Result<BTreeSet<PathBuf>> {
+    let mut paths = BTreeSet::new();
+
+    if let Some(schema_gen_plan) = &plans.schema_gen_plan {
+        if let SchemaSource::Path(path) = schema_gen_plan.source()? {
+            paths.insert(path.to_owned());
+        }
+    }
+
+    if let Some(typescript_gen_plan) = &plans.typescript_gen_plan {
+        paths.insert(typescript_gen_plan.ast.clone());
+        if let Some(document_paths) = &typescript_gen_plan.document_paths {
+            paths.extend(document_paths.resolve(ctx.config_location.as_deref())?);
+        }
+    }
+
+    // An entry rewrites its own schema outputs every run, so they mustn't trigger it again
+    if let Some(schema_gen_plan) = &plans.schema_gen_plan {
+        if let SchemaSource::Path(_) = schema_gen_plan.source()? {
+            for out_path in [
+                &schema_gen_plan.out.ast_path,
+                &schema_gen_plan.out.json_path,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                paths.remove(out_path);
+            }
+        }
+    }
+
+    Ok(paths)
+}
+
+fn take_entry_snapshot(ctx: &app::Context, plans: &GenPlans) -> Result<EntrySnapshot> {
+    let paths = match watched_paths(ctx, plans) {
+        Ok(paths) => paths,
+        Err(err) => return Ok(Err(err.to_string())),
+    };
+
+    let modified_times = paths
+        .into_iter()
+        .map(|path| {
+            let modified = cross::fs::modified(&path)?;
+            Ok((path, modified))
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(Ok(modified_times))
+}
+
+fn take_snapshot(ctx: &app::Context, config_path: &Path, config: &Config) -> Result<Snapshot> {
+    let entries = config
+        .generates
+        .iter()
+        .map(|(name, plans)| Ok((name.clone(), take_entry_snapshot(ctx, plans)?)))
+        .collect::<Result<_>>()?;
+
+    Ok(Snapshot {
+        config: cross::fs::modified(config_path)?,
+        entries,
+    })
+}
+
+async fn run_entries<'a>(
+    ctx: &app::Context,
+    config: &Config,
+    names: impl IntoIterator<Item = &'a String>,
+) {
+    for name in names {
+        let Some(plans) = config.generates.get(name) else {
+            continue;
+        };
+
+        match crate::run_gen_plans(ctx, &cli::Mode::Watch, name, plans, &mut Output::Write).await {
+            Ok(()) => {
+                cross_println!("{} {name}", console::style("Generated").green());
+            }
+            Err(err) => {
+                cross_eprintln!("{} {name}: {err}", console::style("Error").red());
+            }
+        }
+    }
+}
+
+pub async fn watch(
+    ctx: &app::Context,
+    config_path: &Path,
+    selection: &cli::Selection,
+    mut config: Config,
+) -> Result<()> {
+    let mut snapshot = take_snapshot(ctx, config_path, &config)?;
+    run_entries(ctx, &config, snapshot.entries.keys()).await;
+
+    cross_println!("Watching for changes...");
+
+    loop {
+        cross::time::sleep(POLL_INTERVAL_MILLIS).await;
+
+        let new_snapshot = match take_snapshot(ctx, config_path, &config) {
+            Ok(new_snapshot) => new_snapshot,
+            Err(err) => {
+                cross_eprintln!("{} {err}", console::style("Error").red());
+                continue;
+            }
+        };
+
+        if new_snapshot == snapshot {
+            continue;
+        }
+
+        if new_snapshot.config != snapshot.config {
+            print_info!(ctx, 1, "Config changed, reloading...");
+            match Config::load(config_path).and_then(|mut new_config| {
+                new_config.select(selection)?;
+                Ok(new_config)
+            }) {
+                Ok(new_config) => config = new_config,
+                Err(err) => {
+                    cross_eprintln!(
+                        "{} Failed to load config: {err}",
+                        console::style("Error").red()
+                    );
+                    snapshot = new_snapshot;
+                    continue;
+                }
+            }
+
+            // The reloaded config may watch an entirely different set of files
+            snapshot = match take_snapshot(ctx, config_path, &config) {
+                Ok(snapshot) => snapshot,
+                Err(err) => {
+                    cross_eprintln!("{} {err}", console::style("Error").red());
+                    continue;
+                }
+            };
+            run_entries(ctx, &config, snapshot.entries.keys()).await;
+            continue;
+        }
+
+        let changed_entries = new_snapshot
+            .entries
+            .iter()
+            .filter(|(name, entry_snapshot)| snapshot.entries.get(*name) != Some(entry_snapshot))
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+
+        run_entries(ctx, &config, changed_entries).await;
+
+        snapshot = new_snapshot;
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use std::path::PathBuf;
+
+    use crate::app::{self, config::GenPlans};
+
+    #[test]
+    fn watched_paths_skip_own_schema_outputs() {
+        let ctx = app::Context {
+            verbose: 0,
+            config_location: None,
+            log: None,
+        };
+
+        let plans: GenPlans = serde_yaml::from_str(
+            r#"
+schema:
+  path: fixtures/star-wars-introspection-response.json
+  out:
+    ast: schema.graphql
+typescript:
+  ast: schema.graphql
+  documents: ../../examples/app/*.graphql
+  out: generated.ts
+"#,
+        )
+        .expect("deserializing");
+
+        let paths = super::watched_paths(&ctx, &plans).expect("finding watched paths");
+
+        assert_eq!(
+            BTreeSet::from([
+                PathBuf::from("../../examples/app/fragments.graphql"),
+                PathBuf::from("../../examples/app/queries.graphql"),
+                PathBuf::from("fixtures/star-wars-introspection-response.json"),
+            ]),
+            paths
+        );
+    }
+}